@@ -0,0 +1,311 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::read_to_string,
+    io,
+};
+
+use crate::{Direction, Grid, Position};
+
+// Aho-Corasick automaton: a trie of the words plus failure links, so every
+// line of the grid only has to be read once no matter how many words there are
+#[derive(Debug)]
+pub struct Dictionary {
+    words: Vec<String>,
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<char, usize>,
+    // longest proper suffix of this node which is also in the trie
+    fail: usize,
+    // indexes into `words` of every word ending here, including via `fail`
+    output: Vec<usize>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct WordMatch {
+    pub word: String,
+    // position of the first letter
+    pub position: Position,
+    pub direction: Direction,
+}
+
+impl Dictionary {
+    pub fn new<S: AsRef<str>>(words: &[S]) -> Self {
+        let mut dictionary = Dictionary {
+            words: vec![],
+            nodes: vec![Node::default()],
+        };
+
+        let mut seen: HashSet<&str> = HashSet::new();
+        for word in words {
+            let word = word.as_ref().trim();
+            if !word.is_empty() && seen.insert(word) {
+                dictionary.insert(word);
+            }
+        }
+        dictionary.link();
+
+        dictionary
+    }
+
+    // one word per line, blank lines are ignored
+    pub fn from_file(filename: &str) -> Result<Self, io::Error> {
+        let input = read_to_string(filename)?;
+        let words: Vec<&str> = input.lines().collect();
+        Ok(Dictionary::new(&words))
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    fn insert(&mut self, word: &str) {
+        let mut current = 0;
+        for letter in word.chars() {
+            current = match self.nodes[current].children.get(&letter) {
+                Some(next) => *next,
+                None => {
+                    self.nodes.push(Node::default());
+                    let next = self.nodes.len() - 1;
+                    self.nodes[current].children.insert(letter, next);
+                    next
+                }
+            };
+        }
+
+        self.nodes[current].output.push(self.words.len());
+        self.words.push(word.to_string());
+    }
+
+    // bfs so parents' fail links are always set before their children
+    fn link(&mut self) {
+        let mut queue: VecDeque<usize> = self.nodes[0].children.values().copied().collect();
+
+        while let Some(current) = queue.pop_front() {
+            let children: Vec<(char, usize)> = self.nodes[current]
+                .children
+                .iter()
+                .map(|(letter, child)| (*letter, *child))
+                .collect();
+
+            for (letter, child) in children {
+                let mut fail = self.nodes[current].fail;
+                while fail != 0 && !self.nodes[fail].children.contains_key(&letter) {
+                    fail = self.nodes[fail].fail;
+                }
+                let fail = self.nodes[fail].children.get(&letter).copied().unwrap_or(0);
+
+                let inherited = self.nodes[fail].output.clone();
+                self.nodes[child].fail = fail;
+                self.nodes[child].output.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+    }
+
+    fn next(&self, mut state: usize, letter: char) -> usize {
+        loop {
+            if let Some(next) = self.nodes[state].children.get(&letter) {
+                return *next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+}
+
+impl Grid {
+    fn in_grid(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    // every line of cells in this direction, and whether it loops back on itself
+    fn lines(&self, direction: Direction) -> Vec<(Vec<Position>, bool)> {
+        let (dx, dy) = direction.delta();
+        let mut lines = vec![];
+
+        if self.wrap {
+            // each cell is on exactly one loop
+            let mut visited = vec![false; self.grid.len()];
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if visited[(y * self.width) + x] {
+                        continue;
+                    }
+
+                    let mut line = vec![];
+                    let mut current = (x, y);
+                    while !visited[(current.1 * self.width) + current.0] {
+                        visited[(current.1 * self.width) + current.0] = true;
+                        line.push(current);
+                        current = self.step(current, direction, 1);
+                    }
+                    lines.push((line, true));
+                }
+            }
+
+            return lines;
+        }
+
+        for y in 0..self.height as isize {
+            for x in 0..self.width as isize {
+                // only start from cells where a line in this direction enters the grid
+                if self.in_grid(x - dx, y - dy) {
+                    continue;
+                }
+
+                let mut line = vec![];
+                let (mut current_x, mut current_y) = (x, y);
+                while self.in_grid(current_x, current_y) {
+                    line.push((current_x as usize, current_y as usize));
+                    current_x += dx;
+                    current_y += dy;
+                }
+                lines.push((line, false));
+            }
+        }
+
+        lines
+    }
+
+    pub fn find_words(&self, dictionary: &Dictionary) -> Vec<WordMatch> {
+        let mut matches = vec![];
+        let longest = dictionary
+            .words
+            .iter()
+            .map(|word| word.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        for direction in Direction::ALL {
+            for (line, looped) in self.lines(direction) {
+                // go round loops far enough to catch words crossing the start
+                let steps = if looped {
+                    line.len() + longest.saturating_sub(1)
+                } else {
+                    line.len()
+                };
+
+                let mut state = 0;
+                for step in 0..steps {
+                    let (x, y) = line[step % line.len()];
+                    state = dictionary.next(state, self.coord(x, y));
+
+                    for word_index in dictionary.nodes[state].output.iter() {
+                        let word = &dictionary.words[*word_index];
+                        let len = word.chars().count();
+                        // longer than the loop would use a cell twice
+                        if len > line.len() || step + 1 < len {
+                            continue;
+                        }
+
+                        let start = step + 1 - len;
+                        // each start is only reported on its first time round
+                        if start < line.len() {
+                            matches.push(WordMatch {
+                                word: word.clone(),
+                                position: line[start],
+                                direction,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dictionary::{Dictionary, WordMatch},
+        Direction, Grid,
+    };
+
+    const INPUT: &str = "MMMSXXMASM
+          MSAMXMSMSA
+          AMXSXMAAMM
+          MSAMASMSMX
+          XMASAMXAMM
+          XXAMMXXAMA
+          SMSMSASXSS
+          SAXAMASAAA
+          MAMMMXMMMM
+          MXMXAXMASX";
+
+    #[test]
+    fn should_ignore_blank_and_duplicate_words() {
+        let dictionary = Dictionary::new(&["XMAS", "", "  ", "XMAS", "MAS"]);
+        assert_eq!(dictionary.len(), 2);
+    }
+
+    #[test]
+    fn should_find_overlapping_words() {
+        let grid = Grid::parse("XMASAMX").unwrap();
+        let dictionary = Dictionary::new(&["XMAS", "MAS", "SAM", "AS"]);
+        let mut found: Vec<(String, usize, Direction)> = grid
+            .find_words(&dictionary)
+            .into_iter()
+            .filter(|m| m.direction == Direction::East)
+            .map(|m| (m.word, m.position.0, m.direction))
+            .collect();
+        found.sort_by_key(|(word, x, _)| (*x, word.clone()));
+
+        assert_eq!(
+            found,
+            vec![
+                ("XMAS".to_string(), 0, Direction::East),
+                ("MAS".to_string(), 1, Direction::East),
+                ("AS".to_string(), 2, Direction::East),
+                ("SAM".to_string(), 3, Direction::East),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_match_single_word_search() {
+        let grid = Grid::parse(INPUT).unwrap();
+        let words = ["XMAS", "SAMX", "AMXS", "MASAM", "XMASAMX"];
+        let dictionary = Dictionary::new(&words);
+        let found = grid.find_words(&dictionary);
+
+        for word in words {
+            let mut expected: Vec<WordMatch> = grid
+                .find_word(word)
+                .into_iter()
+                .map(|m| WordMatch {
+                    word: word.to_string(),
+                    position: m.position,
+                    direction: m.direction,
+                })
+                .collect();
+            let mut actual: Vec<WordMatch> =
+                found.iter().filter(|m| m.word == word).cloned().collect();
+
+            let key = |m: &WordMatch| (m.position, m.direction as usize);
+            expected.sort_by_key(key);
+            actual.sort_by_key(key);
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn should_match_single_word_search_when_wrapping() {
+        let grid = Grid::parse(INPUT).unwrap().wrapping();
+        let words = ["XMAS", "SAMX", "MMMS", "AMMXMAS"];
+        let dictionary = Dictionary::new(&words);
+        let found = grid.find_words(&dictionary);
+
+        for word in words {
+            let expected = grid.find_word(word).len();
+            assert!(expected > 0);
+            assert_eq!(found.iter().filter(|m| m.word == word).count(), expected);
+        }
+    }
+}
@@ -1,19 +1,105 @@
-use std::{fs::read_to_string, io};
+use std::{
+    env::args,
+    fmt::Display,
+    fs::{read_to_string, write},
+    io,
+};
+
+use dictionary::Dictionary;
+use pattern::{Pattern, X_MAS};
+use render::Overlay;
+
+mod dictionary;
+mod pattern;
+mod render;
 
 fn main() -> Result<(), io::Error> {
+    let args: Vec<String> = args().collect();
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+    };
+
     let input = read_to_string("./input.txt")?;
-    let grid = Grid::new(&input);
+    let grid = if args.iter().any(|arg| arg == "--pad") {
+        Grid::padded(&input, '.')
+    } else {
+        Grid::parse(&input)
+    }
+    .map_err(|err| {
+        let hint = match err {
+            GridError::Ragged { .. } => " (use --pad to fill short rows)",
+            _ => "",
+        };
+        io::Error::new(io::ErrorKind::InvalidData, format!("grid {}{}", err, hint))
+    })?;
+    let grid = if args.iter().any(|arg| arg == "--wrap") {
+        grid.wrapping()
+    } else {
+        grid
+    };
     let _part_one_result = grid.count();
+    let mut overlay = Overlay::new(&grid);
+
+    // word list, one word per line
+    if let Some(filename) = option("--words") {
+        let dictionary = Dictionary::from_file(filename)?;
+        let found = grid.find_words(&dictionary);
+        for word_match in found.iter() {
+            println!(
+                "{} at {:?} going {:?}",
+                word_match.word, word_match.position, word_match.direction
+            );
+        }
+        println!("{} matches for {} words", found.len(), dictionary.len());
+        overlay.add_words(&found);
+    } else {
+        for found in grid.find_word("XMAS") {
+            overlay.add_word("XMAS", &found);
+        }
+    }
 
-    let cross_grid = Grid::with_diagonal_only(&input);
-    let part_two_result = cross_grid.count();
+    // template with `.` as wildcards, defaults to the X-MAS
+    let pattern = match option("--pattern") {
+        Some(filename) => {
+            let pattern = Pattern::from_file(filename)?;
+            println!("Pattern has {} orientations", pattern.variants());
+            for found in grid.find_pattern(&pattern) {
+                println!("Pattern at {:?}", grid.pattern_cells(&pattern, &found));
+            }
+            overlay.add_pattern(&pattern);
+            pattern
+        }
+        None => Pattern::parse(X_MAS)?,
+    };
+    let part_two_result = grid.count_pattern(&pattern);
 
     println!("Total: {:?}", part_two_result);
 
+    // text, colour, html or svg - written to `--out` if given
+    let rendered = match option("--render").map(|mode| mode.as_str()) {
+        Some("text") => Some(overlay.text(false) + "\n"),
+        Some("colour") => Some(overlay.text(true) + "\n"),
+        Some("html") => Some(overlay.html()),
+        Some("svg") => Some(overlay.svg()),
+        Some(mode) => {
+            println!("Unknown render mode {}", mode);
+            None
+        }
+        None => None,
+    };
+    if let Some(rendered) = rendered {
+        match option("--out") {
+            Some(filename) => write(filename, rendered)?,
+            None => print!("{}", rendered),
+        }
+    }
+
     Ok(())
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum Direction {
     North,
     NorthEast,
@@ -25,210 +111,294 @@ enum Direction {
     NorthWest,
 }
 
+impl Direction {
+    const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    // (dx, dy) - y grows downwards
+    fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::NorthEast => (1, -1),
+            Direction::East => (1, 0),
+            Direction::SouthEast => (1, 1),
+            Direction::South => (0, 1),
+            Direction::SouthWest => (-1, 1),
+            Direction::West => (-1, 0),
+            Direction::NorthWest => (-1, -1),
+        }
+    }
+}
+
 type Position = (usize, usize);
 
-fn get_directions(pos: Position, max_x: usize, max_y: usize, cross_only: bool) -> Vec<Direction> {
+// directions a word of `len` letters starting at `pos` can be read in without leaving the grid
+fn get_directions(pos: Position, max_x: usize, max_y: usize, len: usize) -> Vec<Direction> {
     let mut directions = vec![];
-    // 2: it's 0-indexed!
-    // 3: .len() isn't
-    if !cross_only {
-        if pos.0 > 2 {
-            directions.push(Direction::West);
-        }
-        if pos.0 < max_x - 3 {
-            directions.push(Direction::East);
-        }
-        if pos.1 > 2 {
-            directions.push(Direction::North);
-        }
-
-        if pos.1 < max_y - 3 {
-            directions.push(Direction::South);
-        }
+    if len == 0 {
+        return directions;
     }
 
-    if pos.0 > 2 && pos.1 > 2 {
+    // number of cells after the first letter
+    let reach = len - 1;
+    let west = pos.0 >= reach;
+    let east = pos.0 + reach < max_x;
+    let north = pos.1 >= reach;
+    let south = pos.1 + reach < max_y;
+
+    if west {
+        directions.push(Direction::West);
+    }
+    if east {
+        directions.push(Direction::East);
+    }
+    if north {
+        directions.push(Direction::North);
+    }
+    if south {
+        directions.push(Direction::South);
+    }
+    if west && north {
         directions.push(Direction::NorthWest);
     }
-
-    if pos.0 > 2 && pos.1 < max_y - 3 {
+    if west && south {
         directions.push(Direction::SouthWest)
     }
-
-    if pos.0 < max_x - 3 && pos.1 > 2 {
+    if east && north {
         directions.push(Direction::NorthEast);
     }
-
-    if pos.0 < max_x - 3 && pos.1 < max_y - 3 {
+    if east && south {
         directions.push(Direction::SouthEast)
     }
 
     directions
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+struct Match {
+    // position of the first letter
+    position: Position,
+    direction: Direction,
+}
+
+#[derive(Debug, PartialEq)]
+// for the grid and pattern templates alike
+enum GridError {
+    Empty,
+    // row index and its width, compared to the first row's
+    Ragged {
+        row: usize,
+        width: usize,
+        expected: usize,
+    },
+    // input line number, counting from 1
+    BlankLine(usize),
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "is empty"),
+            GridError::Ragged {
+                row,
+                width,
+                expected,
+            } => write!(f, "row {} is {} wide, expected {}", row, width, expected),
+            GridError::BlankLine(line) => write!(f, "has a blank line at {}", line),
+        }
+    }
+}
+
+impl From<GridError> for io::Error {
+    fn from(err: GridError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+    }
+}
+
+// blank lines are skipped, everything else is trimmed
+// blank lines are fine before and after the grid, but not in the middle of it
+fn get_rows(input: &str) -> Result<Vec<Vec<char>>, GridError> {
+    let lines: Vec<&str> = input.lines().map(|line| line.trim()).collect();
+    let Some(first) = lines.iter().position(|line| !line.is_empty()) else {
+        return Err(GridError::Empty);
+    };
+    let last = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .unwrap_or(first);
+
+    lines[first..=last]
+        .iter()
+        .enumerate()
+        .map(|(index, line)| match line.is_empty() {
+            true => Err(GridError::BlankLine(first + index + 1)),
+            false => Ok(line.chars().collect()),
+        })
+        .collect()
+}
+
+// every row has to be as wide as the first, which is the width
+fn check_rows(rows: &[Vec<char>]) -> Result<usize, GridError> {
+    let Some(expected) = rows.first().map(|row| row.len()) else {
+        return Err(GridError::Empty);
+    };
+    match rows.iter().enumerate().find(|(_, r)| r.len() != expected) {
+        Some((row, cells)) => Err(GridError::Ragged {
+            row,
+            width: cells.len(),
+            expected,
+        }),
+        None => Ok(expected),
+    }
+}
+
+#[derive(Debug)]
 struct Grid {
     grid: Vec<char>,
     width: usize,
     height: usize,
-    cross_only: bool,
+    // toroidal: words can run off one edge and continue from the opposite one
+    wrap: bool,
 }
 
 impl Grid {
-    fn new(input: &str) -> Self {
-        let mut height = 0;
-        let matrix: Vec<char> = input
-            .lines()
-            .flat_map(|line| {
-                height += 1;
-                line.trim().chars().collect::<Vec<_>>()
-            })
-            .collect();
-
-        let width = matrix.len() / height;
+    fn parse(input: &str) -> Result<Self, GridError> {
+        let rows = get_rows(input)?;
+        check_rows(&rows)?;
 
-        Grid {
-            grid: matrix,
-            height,
-            width,
-            cross_only: false,
-        }
+        Ok(Grid::from_rows(rows))
     }
 
-    fn with_diagonal_only(input: &str) -> Self {
-        let grid = Grid::new(&input);
+    // short rows are filled up to the longest one
+    fn padded(input: &str, fill: char) -> Result<Self, GridError> {
+        let mut rows = get_rows(input)?;
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        for row in rows.iter_mut() {
+            row.resize(width, fill);
+        }
+
+        Ok(Grid::from_rows(rows))
+    }
 
+    fn from_rows(rows: Vec<Vec<char>>) -> Self {
         Grid {
-            grid: grid.grid,
-            width: grid.width,
-            height: grid.height,
-            cross_only: true,
+            width: rows[0].len(),
+            height: rows.len(),
+            grid: rows.into_iter().flatten().collect(),
+            wrap: false,
         }
     }
 
+    fn wrapping(self) -> Self {
+        Grid { wrap: true, ..self }
+    }
+
     fn coord(&self, x: usize, y: usize) -> char {
         self.grid[(y * self.width) + x]
     }
 
-    fn count(&self) -> u32 {
-        let mut total = 0;
-        let search_char = if self.cross_only { 'A' } else { 'X' };
-
-        if self.cross_only {
-            for y in 0..self.height {
-                for x in 0..self.width {
-                    if self.coord(x, y) == search_char {
-                        // check both diags for SAM and MAS
-                        if y > 0 && y < self.height - 1 && x > 0 && x < self.width - 1 {
-                            // top-left to bottom-right
-                            let up_left = (self.coord(x - 1, y - 1) == 'S'
-                                && self.coord(x + 1, y + 1) == 'M')
-                                || (self.coord(x - 1, y - 1) == 'M'
-                                    && self.coord(x + 1, y + 1) == 'S');
-
-                            // top-right to bottom-left
-                            let up_right = (self.coord(x + 1, y - 1) == 'M'
-                                && self.coord(x - 1, y + 1) == 'S')
-                                || (self.coord(x + 1, y - 1) == 'S'
-                                    && self.coord(x - 1, y + 1) == 'M');
-
-                            if up_left && up_right {
-                                total += 1;
-                            }
-                        }
-                    }
-                }
-            }
+    // without wrapping, caller makes sure the step stays inside the grid
+    fn step(&self, pos: Position, direction: Direction, distance: usize) -> Position {
+        let (dx, dy) = direction.delta();
+        let x = pos.0 as isize + dx * distance as isize;
+        let y = pos.1 as isize + dy * distance as isize;
+
+        if self.wrap {
+            (
+                x.rem_euclid(self.width as isize) as usize,
+                y.rem_euclid(self.height as isize) as usize,
+            )
+        } else {
+            (x as usize, y as usize)
+        }
+    }
+
+    // how many steps before a line in this direction comes back to where it started
+    fn period(&self, direction: Direction) -> usize {
+        match direction.delta() {
+            (0, _) => self.height,
+            (_, 0) => self.width,
+            _ => lcm(self.width, self.height),
+        }
+    }
+
+    fn directions(&self, pos: Position, len: usize) -> Vec<Direction> {
+        if !self.wrap {
+            return get_directions(pos, self.width, self.height, len);
+        }
 
-            return total;
+        // any direction works, as long as no cell is used twice
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| len > 0 && len <= self.period(*direction))
+            .collect()
+    }
+
+    fn find_word(&self, word: &str) -> Vec<Match> {
+        let letters: Vec<char> = word.chars().collect();
+        let mut matches = vec![];
+
+        if letters.is_empty() {
+            return matches;
         }
 
         for y in 0..self.height {
             for x in 0..self.width {
-                if self.coord(x, y) == search_char {
-                    let directions =
-                        get_directions((x, y), self.width, self.height, self.cross_only);
-                    for direction in directions {
-                        match direction {
-                            Direction::North => {
-                                if self.coord(x, y - 1) == 'M'
-                                    && self.coord(x, y - 2) == 'A'
-                                    && self.coord(x, y - 3) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-                            Direction::West => {
-                                if self.coord(x - 1, y) == 'M'
-                                    && self.coord(x - 2, y) == 'A'
-                                    && self.coord(x - 3, y) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-                            Direction::South => {
-                                if self.coord(x, y + 1) == 'M'
-                                    && self.coord(x, y + 2) == 'A'
-                                    && self.coord(x, y + 3) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-                            Direction::East => {
-                                if self.coord(x + 1, y) == 'M'
-                                    && self.coord(x + 2, y) == 'A'
-                                    && self.coord(x + 3, y) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-                            Direction::NorthWest => {
-                                if self.coord(x - 1, y - 1) == 'M'
-                                    && self.coord(x - 2, y - 2) == 'A'
-                                    && self.coord(x - 3, y - 3) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-
-                            Direction::SouthWest => {
-                                if self.coord(x - 1, y + 1) == 'M'
-                                    && self.coord(x - 2, y + 2) == 'A'
-                                    && self.coord(x - 3, y + 3) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-
-                            Direction::SouthEast => {
-                                if self.coord(x + 1, y + 1) == 'M'
-                                    && self.coord(x + 2, y + 2) == 'A'
-                                    && self.coord(x + 3, y + 3) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-
-                            Direction::NorthEast => {
-                                if self.coord(x + 1, y - 1) == 'M'
-                                    && self.coord(x + 2, y - 2) == 'A'
-                                    && self.coord(x + 3, y - 3) == 'S'
-                                {
-                                    total += 1;
-                                }
-                            }
-                        }
+                if self.coord(x, y) != letters[0] {
+                    continue;
+                }
+
+                for direction in self.directions((x, y), letters.len()) {
+                    let found = letters.iter().enumerate().skip(1).all(|(i, letter)| {
+                        let (next_x, next_y) = self.step((x, y), direction, i);
+                        self.coord(next_x, next_y) == *letter
+                    });
+
+                    if found {
+                        matches.push(Match {
+                            position: (x, y),
+                            direction,
+                        });
                     }
                 }
             }
         }
 
-        total
+        matches
+    }
+
+    fn count(&self) -> u32 {
+        self.find_word("XMAS").len() as u32
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
 }
 
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{get_directions, Direction, Grid};
+    use crate::{
+        get_directions,
+        pattern::{Pattern, X_MAS},
+        Direction, Grid, GridError, Match,
+    };
 
     #[test]
     fn input_to_matrix() {
@@ -242,7 +412,7 @@ mod tests {
           SAXAMASAAA
           MAMMMXMMMM
           MXMXAXMASX";
-        let grid = Grid::new(&input);
+        let grid = Grid::parse(input).unwrap();
         assert_eq!(
             grid.grid,
             vec![
@@ -269,18 +439,18 @@ mod tests {
           SAXAMASAAA
           MAMMMXMMMM
           MXMXAXMASX";
-        let grid = Grid::new(&input);
+        let grid = Grid::parse(input).unwrap();
         assert_eq!(grid.count(), 18);
     }
 
     #[test]
     fn should_return_directions() {
         assert_eq!(
-            get_directions((0, 0), 8, 8, false),
+            get_directions((0, 0), 8, 8, 4),
             vec![Direction::East, Direction::South, Direction::SouthEast,]
         );
         assert_eq!(
-            get_directions((4, 0), 8, 8, false),
+            get_directions((4, 0), 8, 8, 4),
             vec![
                 Direction::West,
                 Direction::East,
@@ -290,11 +460,11 @@ mod tests {
             ]
         );
         assert_eq!(
-            get_directions((7, 0), 8, 8, false),
+            get_directions((7, 0), 8, 8, 4),
             vec![Direction::West, Direction::South, Direction::SouthWest,]
         );
         assert_eq!(
-            get_directions((0, 4), 10, 10, false),
+            get_directions((0, 4), 10, 10, 4),
             vec![
                 Direction::East,
                 Direction::North,
@@ -304,7 +474,7 @@ mod tests {
             ]
         );
         assert_eq!(
-            get_directions((4, 4), 10, 10, false),
+            get_directions((4, 4), 10, 10, 4),
             vec![
                 Direction::West,
                 Direction::East,
@@ -317,7 +487,7 @@ mod tests {
             ]
         );
         assert_eq!(
-            get_directions((7, 4), 10, 10, false),
+            get_directions((7, 4), 10, 10, 4),
             vec![
                 Direction::West,
                 Direction::North,
@@ -327,12 +497,12 @@ mod tests {
             ]
         );
         assert_eq!(
-            get_directions((0, 7), 10, 10, false),
+            get_directions((0, 7), 10, 10, 4),
             vec![Direction::East, Direction::North, Direction::NorthEast,]
         );
 
         assert_eq!(
-            get_directions((4, 7), 10, 10, false),
+            get_directions((4, 7), 10, 10, 4),
             vec![
                 Direction::West,
                 Direction::East,
@@ -342,11 +512,63 @@ mod tests {
             ]
         );
         assert_eq!(
-            get_directions((7, 7), 10, 10, false),
+            get_directions((7, 7), 10, 10, 4),
             vec![Direction::West, Direction::North, Direction::NorthWest,]
         );
     }
 
+    #[test]
+    fn should_find_word_positions() {
+        let input = "..X...
+          .SAMX.
+          .A..A.
+          XMAS.S
+          .X....";
+        let grid = Grid::parse(input).unwrap();
+        assert_eq!(
+            grid.find_word("XMAS"),
+            vec![
+                Match {
+                    position: (2, 0),
+                    direction: Direction::SouthEast
+                },
+                Match {
+                    position: (4, 1),
+                    direction: Direction::West
+                },
+                Match {
+                    position: (0, 3),
+                    direction: Direction::East
+                },
+                Match {
+                    position: (1, 4),
+                    direction: Direction::North
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_find_any_length_word() {
+        let input = "MMMSXXMASM
+          MSAMXMSMSA
+          AMXSXMAAMM
+          MSAMASMSMX
+          XMASAMXAMM
+          XXAMMXXAMA
+          SMSMSASXSS
+          SAXAMASAAA
+          MAMMMXMMMM
+          MXMXAXMASX";
+        let grid = Grid::parse(input).unwrap();
+        assert_eq!(grid.find_word("SAMX").len(), 18);
+        assert_eq!(grid.find_word("AMXS").len(), 3);
+        // palindromes are found once per reading direction
+        assert_eq!(grid.find_word("XMASAMX").len(), 6);
+        assert_eq!(grid.find_word("XMASAMXAMMZ").len(), 0);
+        assert_eq!(grid.find_word("").len(), 0);
+    }
+
     #[test]
     fn should_find_cross_mas() {
         let input = "MMMSXXMASM
@@ -359,7 +581,80 @@ mod tests {
           SAXAMASAAA
           MAMMMXMMMM
           MXMXAXMASX";
-        let grid = Grid::with_diagonal_only(&input);
-        assert_eq!(grid.count(), 9);
+        let grid = Grid::parse(input).unwrap();
+        let pattern = Pattern::parse(X_MAS).unwrap();
+        assert_eq!(grid.count_pattern(&pattern), 9);
+    }
+
+    #[test]
+    fn should_reject_ragged_grid() {
+        let input = "XMAS
+          XMA
+          XMAS";
+        assert_eq!(
+            Grid::parse(input).unwrap_err(),
+            GridError::Ragged {
+                row: 1,
+                width: 3,
+                expected: 4
+            }
+        );
+        assert_eq!(Grid::parse("\n  \n").unwrap_err(), GridError::Empty);
+    }
+
+    #[test]
+    fn should_reject_blank_line_inside_grid() {
+        let input = "
+          XMAS
+
+          SAMX
+          ";
+        assert_eq!(Grid::parse(input).unwrap_err(), GridError::BlankLine(3));
+        assert_eq!(
+            Grid::padded(input, '.').unwrap_err(),
+            GridError::BlankLine(3)
+        );
+
+        // only around the grid
+        let grid = Grid::parse("\n\nXMAS\nSAMX\n\n").unwrap();
+        assert_eq!((grid.width, grid.height), (4, 2));
+    }
+
+    #[test]
+    fn should_pad_ragged_grid() {
+        let input = "XMAS
+          XMA
+          X";
+        let grid = Grid::padded(input, '.').unwrap();
+        assert_eq!((grid.width, grid.height), (4, 3));
+        assert_eq!(grid.grid.iter().collect::<String>(), "XMASXMA.X...");
+    }
+
+    #[test]
+    fn should_find_words_across_edges_when_wrapping() {
+        let input = "ASXM
+          ....
+          S...
+          A...";
+        assert_eq!(Grid::parse(input).unwrap().find_word("XMAS"), vec![]);
+
+        let grid = Grid::parse(input).unwrap().wrapping();
+        assert_eq!(
+            grid.find_word("XMAS"),
+            vec![Match {
+                position: (2, 0),
+                direction: Direction::East
+            }]
+        );
+        // north from the top row comes back in at the bottom
+        assert_eq!(
+            grid.find_word("AAS"),
+            vec![Match {
+                position: (0, 0),
+                direction: Direction::North
+            }]
+        );
+        // would need to use a cell twice
+        assert_eq!(grid.find_word("XMASX"), vec![]);
     }
 }
@@ -0,0 +1,257 @@
+use std::{fs::read_to_string, io};
+
+use crate::{check_rows, Grid, GridError, Position};
+
+pub const WILDCARD: char = '.';
+
+// the X-MAS from part two
+pub const X_MAS: &str = "M.S/.A./M.S";
+
+// a single orientation of a pattern, `None` is a wildcard
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Template {
+    cells: Vec<Option<char>>,
+    width: usize,
+    height: usize,
+}
+
+impl Template {
+    fn get(&self, x: usize, y: usize) -> Option<char> {
+        self.cells[(y * self.width) + x]
+    }
+
+    // 90 degrees clockwise
+    fn rotate(&self) -> Template {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..self.width {
+            for x in 0..self.height {
+                cells.push(self.get(y, self.height - 1 - x));
+            }
+        }
+
+        Template {
+            cells,
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    // mirror left to right
+    fn reflect(&self) -> Template {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                cells.push(self.get(self.width - 1 - x, y));
+            }
+        }
+
+        Template {
+            cells,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    // positions of the non-wildcard cells, relative to the top-left corner
+    fn fixed(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(index, cell)| cell.map(|c| (index % self.width, index / self.width, c)))
+    }
+}
+
+#[derive(Debug)]
+pub struct Pattern {
+    // every distinct rotation and reflection
+    variants: Vec<Template>,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct PatternMatch {
+    // top-left corner of the matched variant
+    pub position: Position,
+    pub variant: usize,
+}
+
+impl Pattern {
+    // rows are separated by newlines or `/`
+    pub fn parse(input: &str) -> Result<Self, GridError> {
+        let rows: Vec<Vec<char>> = input
+            .split(['\n', '/'])
+            .map(|row| row.trim())
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars().collect())
+            .collect();
+
+        let width = check_rows(&rows)?;
+
+        let template = Template {
+            cells: rows
+                .iter()
+                .flatten()
+                .map(|c| if *c == WILDCARD { None } else { Some(*c) })
+                .collect(),
+            width,
+            height: rows.len(),
+        };
+
+        let mut variants: Vec<Template> = vec![];
+        let mut current = template;
+        for _ in 0..4 {
+            for candidate in [current.clone(), current.reflect()] {
+                if !variants.contains(&candidate) {
+                    variants.push(candidate);
+                }
+            }
+            current = current.rotate();
+        }
+
+        Ok(Pattern { variants })
+    }
+
+    pub fn from_file(filename: &str) -> Result<Self, io::Error> {
+        let input = read_to_string(filename)?;
+        Pattern::parse(&input)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("pattern {}", err)))
+    }
+
+    pub fn variants(&self) -> usize {
+        self.variants.len()
+    }
+}
+
+impl Grid {
+    // wraps round when the grid does
+    fn offset(&self, pos: Position, dx: usize, dy: usize) -> Position {
+        if self.wrap {
+            ((pos.0 + dx) % self.width, (pos.1 + dy) % self.height)
+        } else {
+            (pos.0 + dx, pos.1 + dy)
+        }
+    }
+
+    // grid positions covered by the letters of a match
+    pub fn pattern_cells(&self, pattern: &Pattern, found: &PatternMatch) -> Vec<Position> {
+        pattern.variants[found.variant]
+            .fixed()
+            .map(|(dx, dy, _)| self.offset(found.position, dx, dy))
+            .collect()
+    }
+
+    pub fn find_pattern(&self, pattern: &Pattern) -> Vec<PatternMatch> {
+        let mut matches = vec![];
+
+        for (variant, template) in pattern.variants.iter().enumerate() {
+            // bigger than the grid would mean using a cell twice, even when wrapping
+            if template.width > self.width || template.height > self.height {
+                continue;
+            }
+
+            // when wrapping, the corner can be anywhere
+            let (max_x, max_y) = if self.wrap {
+                (self.width - 1, self.height - 1)
+            } else {
+                (self.width - template.width, self.height - template.height)
+            };
+
+            for y in 0..=max_y {
+                for x in 0..=max_x {
+                    let found = template.fixed().all(|(dx, dy, letter)| {
+                        let (next_x, next_y) = self.offset((x, y), dx, dy);
+                        self.coord(next_x, next_y) == letter
+                    });
+
+                    if found {
+                        matches.push(PatternMatch {
+                            position: (x, y),
+                            variant,
+                        });
+                    }
+                }
+            }
+        }
+
+        matches.sort_by_key(|m| (m.position.1, m.position.0, m.variant));
+        matches
+    }
+
+    pub fn count_pattern(&self, pattern: &Pattern) -> usize {
+        self.find_pattern(pattern).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        pattern::{Pattern, PatternMatch, X_MAS},
+        Grid, GridError,
+    };
+
+    #[test]
+    fn should_expand_rotations_and_reflections() {
+        // all four X-MAS orientations, reflections are duplicates
+        assert_eq!(Pattern::parse(X_MAS).unwrap().variants(), 4);
+        // horizontal and vertical, both ways round
+        assert_eq!(Pattern::parse("XMAS").unwrap().variants(), 4);
+        // an L shape has no symmetry
+        assert_eq!(Pattern::parse("X./XM").unwrap().variants(), 8);
+        assert_eq!(Pattern::parse("A").unwrap().variants(), 1);
+    }
+
+    #[test]
+    fn should_reject_bad_patterns() {
+        assert_eq!(Pattern::parse(" / ").unwrap_err(), GridError::Empty);
+        assert_eq!(
+            Pattern::parse("M.S\n.A\nM.S").unwrap_err(),
+            GridError::Ragged {
+                row: 1,
+                width: 2,
+                expected: 3
+            }
+        );
+    }
+
+    #[test]
+    fn should_locate_pattern() {
+        let grid = Grid::parse(
+            "M.S.
+             .A..
+             M.S.",
+        )
+        .unwrap();
+        let pattern = Pattern::parse(X_MAS).unwrap();
+        let found = grid.find_pattern(&pattern);
+
+        assert_eq!(
+            found,
+            vec![PatternMatch {
+                position: (0, 0),
+                variant: 0
+            }]
+        );
+        assert_eq!(
+            grid.pattern_cells(&pattern, &found[0]),
+            vec![(0, 0), (2, 0), (1, 1), (0, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn should_wrap_pattern_round_edges() {
+        // centred on the top-left corner
+        let input = "A...
+                     .S.M
+                     ....
+                     .S.M";
+        let pattern = Pattern::parse(X_MAS).unwrap();
+        assert_eq!(Grid::parse(input).unwrap().count_pattern(&pattern), 0);
+
+        let grid = Grid::parse(input).unwrap().wrapping();
+        let found = grid.find_pattern(&pattern);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            grid.pattern_cells(&pattern, &found[0]),
+            vec![(3, 3), (1, 3), (0, 0), (3, 1), (1, 1)]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{dictionary::WordMatch, pattern::Pattern, Direction, Grid, Match};
+
+pub const EMPTY: char = '.';
+
+// ansi code and svg fill for each direction
+fn colour(direction: Option<Direction>) -> (u8, &'static str) {
+    match direction {
+        Some(Direction::North) => (31, "#d62728"),
+        Some(Direction::NorthEast) => (33, "#ff7f0e"),
+        Some(Direction::East) => (32, "#2ca02c"),
+        Some(Direction::SouthEast) => (36, "#17becf"),
+        Some(Direction::South) => (34, "#1f77b4"),
+        Some(Direction::SouthWest) => (35, "#9467bd"),
+        Some(Direction::West) => (91, "#e377c2"),
+        Some(Direction::NorthWest) => (93, "#bcbd22"),
+        None => (97, "#333333"),
+    }
+}
+
+fn escape(letter: char) -> String {
+    match letter {
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '&' => "&amp;".to_string(),
+        '"' => "&quot;".to_string(),
+        _ => letter.to_string(),
+    }
+}
+
+// which cells are part of a match, and which way the (latest) match through them goes.
+// patterns don't have a direction
+pub struct Overlay<'a> {
+    grid: &'a Grid,
+    cells: Vec<Option<Option<Direction>>>,
+}
+
+impl<'a> Overlay<'a> {
+    pub fn new(grid: &'a Grid) -> Self {
+        Overlay {
+            grid,
+            cells: vec![None; grid.grid.len()],
+        }
+    }
+
+    fn mark(&mut self, x: usize, y: usize, direction: Option<Direction>) {
+        self.cells[(y * self.grid.width) + x] = Some(direction);
+    }
+
+    fn add_line(&mut self, position: (usize, usize), direction: Direction, len: usize) {
+        for i in 0..len {
+            let (x, y) = self.grid.step(position, direction, i);
+            self.mark(x, y, Some(direction));
+        }
+    }
+
+    pub fn add_word(&mut self, word: &str, found: &Match) {
+        self.add_line(found.position, found.direction, word.chars().count());
+    }
+
+    pub fn add_words(&mut self, found: &[WordMatch]) {
+        for word_match in found {
+            self.add_line(
+                word_match.position,
+                word_match.direction,
+                word_match.word.chars().count(),
+            );
+        }
+    }
+
+    pub fn add_pattern(&mut self, pattern: &Pattern) {
+        for found in self.grid.find_pattern(pattern) {
+            for (x, y) in self.grid.pattern_cells(pattern, &found) {
+                self.mark(x, y, None);
+            }
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = Vec<(char, Option<Option<Direction>>)>> + '_ {
+        (0..self.grid.height).map(move |y| {
+            (0..self.grid.width)
+                .map(|x| (self.grid.coord(x, y), self.cells[(y * self.grid.width) + x]))
+                .collect()
+        })
+    }
+
+    // like the puzzle description: letters which aren't in a match are dots
+    pub fn text(&self, use_colour: bool) -> String {
+        self.rows()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(letter, cell)| match cell {
+                        Some(direction) if use_colour => {
+                            format!("\x1b[{}m{}\x1b[0m", colour(direction).0, letter)
+                        }
+                        Some(_) => letter.to_string(),
+                        None => EMPTY.to_string(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // one coloured span per letter, unmatched letters are greyed out rather than hidden
+    pub fn html(&self) -> String {
+        let body = self
+            .rows()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(letter, cell)| match cell {
+                        Some(direction) => format!(
+                            "<span style=\"color:{}\">{}</span>",
+                            colour(direction).1,
+                            escape(letter)
+                        ),
+                        None => format!("<span class=\"empty\">{}</span>", escape(letter)),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<style>\npre {{ font-family: monospace; line-height: 1; }}\n.empty {{ color: #dddddd; }}\n</style>\n</head>\n<body>\n<pre>\n{}\n</pre>\n</body>\n</html>\n",
+            body
+        )
+    }
+
+    pub fn svg(&self) -> String {
+        const CELL: usize = 12;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"10\" text-anchor=\"middle\">\n",
+            self.grid.width * CELL,
+            self.grid.height * CELL
+        );
+
+        for (y, row) in self.rows().enumerate() {
+            for (x, (letter, cell)) in row.into_iter().enumerate() {
+                if let Some(direction) = cell {
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>\n",
+                        x * CELL + CELL / 2,
+                        y * CELL + CELL - 2,
+                        colour(direction).1,
+                        escape(letter)
+                    ));
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dictionary::Dictionary,
+        pattern::{Pattern, X_MAS},
+        render::Overlay,
+        Grid,
+    };
+
+    const INPUT: &str = "MMXAMA
+          ASAMXM
+          AAMAAM
+          XMASMS
+          MXMMSA";
+
+    #[test]
+    fn should_only_show_matched_letters() {
+        let grid = Grid::parse(INPUT).unwrap();
+        let mut overlay = Overlay::new(&grid);
+        for found in grid.find_word("XMAS") {
+            overlay.add_word("XMAS", &found);
+        }
+
+        assert_eq!(
+            overlay.text(false),
+            "..X...
+.SAMX.
+.A..A.
+XMAS.S
+.X...."
+        );
+    }
+
+    #[test]
+    fn should_colour_by_direction() {
+        let grid = Grid::parse("XMAS").unwrap();
+        let mut overlay = Overlay::new(&grid);
+        overlay.add_words(&grid.find_words(&Dictionary::new(&["XMAS"])));
+
+        assert_eq!(
+            overlay.text(true),
+            "\x1b[32mX\x1b[0m\x1b[32mM\x1b[0m\x1b[32mA\x1b[0m\x1b[32mS\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn should_export_svg_and_html() {
+        let grid = Grid::parse(
+            "M.S
+             XAX
+             M.S",
+        )
+        .unwrap();
+        let mut overlay = Overlay::new(&grid);
+        overlay.add_pattern(&Pattern::parse(X_MAS).unwrap());
+
+        let svg = overlay.svg();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<text").count(), 5);
+
+        let html = overlay.html();
+        assert_eq!(html.matches("class=\"empty\"").count(), 4);
+        assert_eq!(html.matches("color:#333333").count(), 5);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{fmt::Display, io, str::FromStr};
+
+use itertools::Itertools;
+
+// which page counts as the middle when there are two
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EvenPolicy {
+    Lower,
+    Upper,
+    Reject,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Aggregator {
+    // by position in the update
+    Middle(EvenPolicy),
+    First,
+    Last,
+    // by page number, averaging (rounded down) the two middle pages of an even update
+    Median,
+    Sum,
+    // each page times its 1-based position
+    Weighted,
+}
+
+impl Default for Aggregator {
+    // the puzzle's answer: the upper middle, as `values[values.len() / 2]`
+    fn default() -> Self {
+        Aggregator::Middle(EvenPolicy::Upper)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AggregateError {
+    Unknown(String),
+    Empty,
+    EvenLength(Vec<u32>),
+    Overflow,
+}
+
+impl Display for AggregateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AggregateError::Unknown(name) => write!(
+                f,
+                "unknown aggregator {}, expected one of middle, middle-lower, middle-upper, middle-strict, first, last, median, sum, weighted",
+                name
+            ),
+            AggregateError::Empty => write!(f, "update has no pages"),
+            AggregateError::EvenLength(values) => write!(
+                f,
+                "update {} has no single middle page",
+                values.iter().join(",")
+            ),
+            AggregateError::Overflow => write!(f, "total is too big"),
+        }
+    }
+}
+
+impl From<AggregateError> for io::Error {
+    fn from(err: AggregateError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+    }
+}
+
+impl FromStr for Aggregator {
+    type Err = AggregateError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "middle" | "middle-upper" => Ok(Aggregator::Middle(EvenPolicy::Upper)),
+            "middle-lower" => Ok(Aggregator::Middle(EvenPolicy::Lower)),
+            "middle-strict" => Ok(Aggregator::Middle(EvenPolicy::Reject)),
+            "first" => Ok(Aggregator::First),
+            "last" => Ok(Aggregator::Last),
+            "median" => Ok(Aggregator::Median),
+            "sum" => Ok(Aggregator::Sum),
+            "weighted" => Ok(Aggregator::Weighted),
+            _ => Err(AggregateError::Unknown(name.to_string())),
+        }
+    }
+}
+
+impl Aggregator {
+    pub fn apply(&self, values: &[u32]) -> Result<u64, AggregateError> {
+        let (first, last) = match (values.first(), values.last()) {
+            (Some(first), Some(last)) => (*first as u64, *last as u64),
+            _ => return Err(AggregateError::Empty),
+        };
+        let upper = values.len() / 2;
+
+        match self {
+            Aggregator::Middle(policy) => match policy {
+                _ if values.len() % 2 == 1 => Ok(values[upper] as u64),
+                EvenPolicy::Lower => Ok(values[upper - 1] as u64),
+                EvenPolicy::Upper => Ok(values[upper] as u64),
+                EvenPolicy::Reject => Err(AggregateError::EvenLength(values.to_vec())),
+            },
+            Aggregator::First => Ok(first),
+            Aggregator::Last => Ok(last),
+            Aggregator::Median => {
+                let sorted: Vec<u64> = values.iter().map(|v| *v as u64).sorted().collect();
+                if sorted.len() % 2 == 1 {
+                    Ok(sorted[upper])
+                } else {
+                    Ok((sorted[upper - 1] + sorted[upper]) / 2)
+                }
+            }
+            Aggregator::Sum => Ok(values.iter().map(|v| *v as u64).sum()),
+            Aggregator::Weighted => values
+                .iter()
+                .enumerate()
+                .try_fold(0u64, |acc, (index, v)| {
+                    (*v as u64)
+                        .checked_mul(index as u64 + 1)
+                        .and_then(|score| acc.checked_add(score))
+                })
+                .ok_or(AggregateError::Overflow),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aggregate::{AggregateError, Aggregator, EvenPolicy};
+
+    #[test]
+    fn should_pick_middle_by_policy() {
+        let odd = [75, 47, 61, 53, 29];
+        let even = [75, 47, 61, 53];
+
+        for policy in [EvenPolicy::Lower, EvenPolicy::Upper, EvenPolicy::Reject] {
+            assert_eq!(Aggregator::Middle(policy).apply(&odd), Ok(61));
+        }
+        assert_eq!(Aggregator::Middle(EvenPolicy::Lower).apply(&even), Ok(47));
+        assert_eq!(Aggregator::Middle(EvenPolicy::Upper).apply(&even), Ok(61));
+        assert_eq!(
+            Aggregator::Middle(EvenPolicy::Reject).apply(&even),
+            Err(AggregateError::EvenLength(even.to_vec()))
+        );
+    }
+
+    #[test]
+    fn should_aggregate() {
+        let values = [75, 47, 61, 53];
+        assert_eq!(Aggregator::First.apply(&values), Ok(75));
+        assert_eq!(Aggregator::Last.apply(&values), Ok(53));
+        // 53 and 61
+        assert_eq!(Aggregator::Median.apply(&values), Ok(57));
+        assert_eq!(Aggregator::Sum.apply(&values), Ok(236));
+        assert_eq!(
+            Aggregator::Weighted.apply(&values),
+            Ok(75 + 47 * 2 + 61 * 3 + 53 * 4)
+        );
+    }
+
+    #[test]
+    fn should_reject_empty_update() {
+        assert_eq!(Aggregator::default().apply(&[]), Err(AggregateError::Empty));
+        assert_eq!(Aggregator::Sum.apply(&[]), Err(AggregateError::Empty));
+    }
+
+    #[test]
+    fn should_parse_names() {
+        assert_eq!("middle".parse(), Ok(Aggregator::default()));
+        assert_eq!(
+            "middle-strict".parse(),
+            Ok(Aggregator::Middle(EvenPolicy::Reject))
+        );
+        assert_eq!("weighted".parse(), Ok(Aggregator::Weighted));
+        assert_eq!(
+            "mean".parse::<Aggregator>(),
+            Err(AggregateError::Unknown("mean".to_string()))
+        );
+    }
+}
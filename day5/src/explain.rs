@@ -0,0 +1,437 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use itertools::Itertools;
+
+use crate::{sort_update, OrderError, Rule, RuleSet};
+
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub rule: Rule,
+    // where each page of the rule is in the update
+    pub positions: (usize, usize),
+}
+
+// take `page` out of `from` and put it back in at `to`, both indexes into the update as it
+// is just before this move
+#[derive(Debug, PartialEq)]
+pub struct Move {
+    pub page: u32,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub update: Vec<u32>,
+    pub broken: Vec<Violation>,
+    pub fixed: Vec<u32>,
+    // fewest moves to get to any valid order, which `fixed` is one of
+    pub moves: Vec<Move>,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.update.iter().join(","))?;
+        for violation in self.broken.iter() {
+            writeln!(
+                f,
+                "  breaks {}|{} ({} at {}, {} at {})",
+                violation.rule.0,
+                violation.rule.1,
+                violation.rule.0,
+                violation.positions.0,
+                violation.rule.1,
+                violation.positions.1
+            )?;
+        }
+        for step in self.moves.iter() {
+            writeln!(f, "  move {} from {} to {}", step.page, step.from, step.to)?;
+        }
+        write!(f, "  fixed: {}", self.fixed.iter().join(","))
+    }
+}
+
+pub fn get_violations(rules: &RuleSet, update: &[u32]) -> Vec<Violation> {
+    let mut broken = vec![];
+    for (first, a) in update.iter().enumerate() {
+        for (second, b) in update.iter().enumerate().skip(first + 1) {
+            if rules.must_precede(*b, *a) {
+                broken.push(Violation {
+                    rule: (*b, *a),
+                    positions: (second, first),
+                });
+            }
+        }
+    }
+    broken
+}
+
+// pairs of (index in a, index in b) for a longest common subsequence
+fn common_subsequence(a: &[u32], b: &[u32]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+// every page outside the longest run already in the right order has to move, and moving
+// those is enough
+fn get_moves(update: &[u32], fixed: &[u32]) -> Vec<Move> {
+    let kept = common_subsequence(update, fixed);
+
+    // which slot of `fixed` each page of the update ends up in
+    let mut target: Vec<Option<usize>> = vec![None; update.len()];
+    let mut taken = vec![false; fixed.len()];
+    for (i, j) in kept.iter() {
+        target[*i] = Some(*j);
+        taken[*j] = true;
+    }
+    for (i, page) in update.iter().enumerate() {
+        if target[i].is_none() {
+            let j = (0..fixed.len())
+                .find(|j| !taken[*j] && fixed[*j] == *page)
+                .expect("Fixed update must have the same pages");
+            target[i] = Some(j);
+            taken[j] = true;
+        }
+    }
+
+    // (page, slot, in place yet)
+    let mut working: Vec<(u32, usize, bool)> = update
+        .iter()
+        .zip(target.iter())
+        .map(|(page, slot)| (*page, slot.unwrap(), false))
+        .collect();
+    for (i, _) in kept.iter() {
+        working[*i].2 = true;
+    }
+
+    let mut moving: Vec<(u32, usize)> = working
+        .iter()
+        .filter(|(_, _, settled)| !settled)
+        .map(|(page, slot, _)| (*page, *slot))
+        .collect();
+    moving.sort_by_key(|(_, slot)| *slot);
+
+    // in slot order, so the page which belongs just before is always in place already
+    let mut moves = vec![];
+    for (page, slot) in moving {
+        let from = working.iter().position(|(_, s, _)| *s == slot).unwrap();
+        working.remove(from);
+        let to = match slot {
+            0 => 0,
+            _ => working.iter().position(|(_, s, _)| *s == slot - 1).unwrap() + 1,
+        };
+        working.insert(to, (page, slot, true));
+        moves.push(Move { page, from, to });
+    }
+
+    moves
+}
+
+// for each pair of positions, whether the rules put the first page before the second,
+// directly or through other pages in the update
+fn get_order(rules: &RuleSet, update: &[u32]) -> Vec<Vec<bool>> {
+    let mut before: Vec<Vec<bool>> = update
+        .iter()
+        .map(|a| update.iter().map(|b| rules.must_precede(*a, *b)).collect())
+        .collect();
+    for k in 0..update.len() {
+        for i in 0..update.len() {
+            for j in 0..update.len() {
+                before[i][j] = before[i][j] || (before[i][k] && before[k][j]);
+            }
+        }
+    }
+    before
+}
+
+// tries to give position `i` a later position it's out of order with, moving earlier
+// matches along if needed
+fn find_match(
+    i: usize,
+    inverted: &[Vec<bool>],
+    matched: &mut [Option<usize>],
+    seen: &mut [bool],
+) -> bool {
+    for j in 0..inverted.len() {
+        if inverted[i][j] && !seen[j] {
+            seen[j] = true;
+            if matched[j].is_none_or(|other| find_match(other, inverted, matched, seen)) {
+                matched[j] = Some(i);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// the most positions that can stay where they are: no two of them out of order. Being out
+// of order carries over (if a is out with b and b with c, a is with c), so this is the
+// biggest antichain, found from a maximum matching and konig's theorem
+fn get_kept(before: &[Vec<bool>]) -> Vec<bool> {
+    let n = before.len();
+    let inverted: Vec<Vec<bool>> = (0..n)
+        .map(|i| (0..n).map(|j| i < j && before[j][i]).collect())
+        .collect();
+
+    // matched[j] is the earlier position matched with j
+    let mut matched: Vec<Option<usize>> = vec![None; n];
+    let has_match: Vec<bool> = (0..n)
+        .map(|i| find_match(i, &inverted, &mut matched, &mut vec![false; n]))
+        .collect();
+
+    // everything reachable from an unmatched earlier position, going out along any pair
+    // and back along matched ones
+    let mut left: Vec<bool> = has_match.iter().map(|m| !m).collect();
+    let mut right = vec![false; n];
+    let mut queue: Vec<usize> = (0..n).filter(|i| left[*i]).collect();
+    while let Some(i) = queue.pop() {
+        for j in 0..n {
+            if inverted[i][j] && !right[j] {
+                right[j] = true;
+                if let Some(other) = matched[j] {
+                    if !left[other] {
+                        left[other] = true;
+                        queue.push(other);
+                    }
+                }
+            }
+        }
+    }
+
+    (0..n).map(|i| left[i] && !right[i]).collect()
+}
+
+// a valid order which leaves as many pages as possible where they are. Kahn's algorithm
+// again, with each kept page also having to come before the next one
+fn get_fixed(rules: &RuleSet, update: &[u32]) -> Vec<u32> {
+    let before = get_order(rules, update);
+    let kept = get_kept(&before);
+    let n = update.len();
+
+    let mut after: Vec<Vec<usize>> = (0..n)
+        .map(|i| (0..n).filter(|j| before[i][*j]).collect())
+        .collect();
+    let chain: Vec<usize> = (0..n).filter(|i| kept[*i]).collect();
+    for pair in chain.windows(2) {
+        after[pair[0]].push(pair[1]);
+    }
+
+    let mut in_degree = vec![0; n];
+    for later in after.iter().flatten() {
+        in_degree[*later] += 1;
+    }
+    let mut ready: BTreeSet<usize> = (0..n).filter(|i| in_degree[*i] == 0).collect();
+    let mut fixed = vec![];
+    while let Some(current) = ready.pop_first() {
+        fixed.push(update[current]);
+        for later in after[current].iter() {
+            in_degree[*later] -= 1;
+            if in_degree[*later] == 0 {
+                ready.insert(*later);
+            }
+        }
+    }
+    fixed
+}
+
+pub fn explain(rules: &RuleSet, update: &[u32]) -> Result<Report, OrderError> {
+    // only to report a cycle, any other order might need more moves
+    sort_update(rules, update)?;
+    let fixed = get_fixed(rules, update);
+
+    Ok(Report {
+        update: update.to_vec(),
+        broken: get_violations(rules, update),
+        moves: get_moves(update, &fixed),
+        fixed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashSet, VecDeque};
+
+    use itertools::Itertools;
+
+    use crate::{
+        explain::{explain, get_moves, Move, Violation},
+        get_rules, RuleSet,
+    };
+
+    const RULES: &str = "47|53
+        97|13
+        97|61
+        97|47
+        75|29
+        61|13
+        75|53
+        29|13
+        97|29
+        53|29
+        61|53
+        97|53
+        61|29
+        47|13
+        75|47
+        97|75
+        47|61
+        75|61
+        47|29
+        75|13
+        53|13";
+
+    #[test]
+    fn should_list_broken_rules() {
+        let rules = get_rules(RULES).unwrap();
+        let report = explain(&rules, &[61, 13, 29]).unwrap();
+
+        assert_eq!(
+            report.broken,
+            vec![Violation {
+                rule: (29, 13),
+                positions: (2, 1)
+            }]
+        );
+        assert_eq!(report.fixed, vec![61, 29, 13]);
+        assert_eq!(
+            report.moves,
+            vec![Move {
+                page: 13,
+                from: 1,
+                to: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn should_use_fewest_moves() {
+        let rules = get_rules(RULES).unwrap();
+        let report = explain(&rules, &[97, 13, 75, 29, 47]).unwrap();
+
+        assert_eq!(report.broken.len(), 4);
+        assert_eq!(report.fixed, vec![97, 75, 47, 29, 13]);
+        // 97, 75, 47 are already in order
+        assert_eq!(
+            report.moves,
+            vec![
+                Move {
+                    page: 29,
+                    from: 3,
+                    to: 4
+                },
+                Move {
+                    page: 13,
+                    from: 1,
+                    to: 4
+                }
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "97,13,75,29,47
+  breaks 75|13 (75 at 2, 13 at 1)
+  breaks 29|13 (29 at 3, 13 at 1)
+  breaks 47|13 (47 at 4, 13 at 1)
+  breaks 47|29 (47 at 4, 29 at 3)
+  move 29 from 3 to 4
+  move 13 from 1 to 4
+  fixed: 97,75,47,29,13"
+        );
+    }
+
+    #[test]
+    fn should_pick_the_order_nearest_the_update() {
+        let rules = get_rules("0|2\n2|4\n3|4").unwrap();
+        let report = explain(&rules, &[2, 3, 4, 1, 0]).unwrap();
+
+        assert_eq!(report.fixed, vec![0, 2, 3, 4, 1]);
+        assert_eq!(
+            report.moves,
+            vec![Move {
+                page: 0,
+                from: 4,
+                to: 0
+            }]
+        );
+    }
+
+    // moving one page at a time until the update is valid
+    fn fewest_moves(rules: &RuleSet, update: &[u32]) -> usize {
+        let mut seen = HashSet::from([update.to_vec()]);
+        let mut queue = VecDeque::from([(update.to_vec(), 0)]);
+        while let Some((current, moves)) = queue.pop_front() {
+            if rules.is_valid(&current) {
+                return moves;
+            }
+            for from in 0..current.len() {
+                for to in 0..current.len() {
+                    let mut next = current.clone();
+                    let page = next.remove(from);
+                    next.insert(to, page);
+                    if seen.insert(next.clone()) {
+                        queue.push_back((next, moves + 1));
+                    }
+                }
+            }
+        }
+        unreachable!("Some order has to be valid");
+    }
+
+    #[test]
+    fn should_use_fewest_moves_over_every_valid_order() {
+        for rules in ["0|2\n2|4\n3|4", "0|1\n2|3\n4|1\n4|3", "1|0\n2|0\n3|4\n0|4"] {
+            let rules = get_rules(rules).unwrap();
+            for update in (0..5).permutations(5) {
+                let report = explain(&rules, &update).unwrap();
+                let mut working = update.clone();
+                for step in report.moves.iter() {
+                    assert_eq!(working.remove(step.from), step.page);
+                    working.insert(step.to, step.page);
+                }
+
+                assert_eq!(working, report.fixed);
+                assert!(rules.is_valid(&report.fixed));
+                assert_eq!(report.moves.len(), fewest_moves(&rules, &update));
+            }
+        }
+    }
+
+    #[test]
+    fn moves_should_produce_fixed_update() {
+        let update = vec![5, 1, 4, 2, 3, 2];
+        let fixed = vec![1, 2, 2, 3, 4, 5];
+        let mut working = update.clone();
+        let moves = get_moves(&update, &fixed);
+        for step in moves.iter() {
+            assert_eq!(working.remove(step.from), step.page);
+            working.insert(step.to, step.page);
+        }
+
+        assert_eq!(working, fixed);
+        // 1, 2, 3 stay put
+        assert_eq!(moves.len(), 3);
+    }
+}
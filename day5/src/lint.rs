@@ -0,0 +1,337 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
+
+use itertools::Itertools;
+
+use crate::{find_cycle, OrderError, Rule, RuleSet, Update};
+
+#[derive(Debug, PartialEq)]
+pub enum Lint {
+    // 1-based position of every copy among the rules
+    Duplicate { rule: Rule, lines: Vec<usize> },
+    // both `a|b` and `b|a`
+    Contradiction(Rule),
+    // each page has to come before the next, and the last one before the first
+    Cycle(Vec<u32>),
+    // follows from the chain of pages in `via`, which starts and ends with the rule's, but
+    // only in updates which have every page of the chain, since rules for missing pages
+    // are ignored
+    Implied { rule: Rule, via: Vec<u32> },
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lint::Duplicate { rule, lines } => write!(
+                f,
+                "duplicate rule {}|{} (rules {})",
+                rule.0,
+                rule.1,
+                lines.iter().join(", ")
+            ),
+            Lint::Contradiction(rule) => write!(
+                f,
+                "contradiction: {}|{} and {}|{}",
+                rule.0, rule.1, rule.1, rule.0
+            ),
+            Lint::Cycle(pages) => write!(f, "{}", OrderError::Cycle(pages.clone())),
+            Lint::Implied { rule, via } => write!(
+                f,
+                "rule {}|{} is implied by {} when those pages are present",
+                rule.0,
+                rule.1,
+                via.iter().join("|")
+            ),
+        }
+    }
+}
+
+// strongly connected components with more than one page, or a page which has to come before
+// itself (tarjan's)
+fn get_loops(rules: &RuleSet) -> Vec<Vec<u32>> {
+    struct State {
+        index: HashMap<u32, usize>,
+        low: HashMap<u32, usize>,
+        stack: Vec<u32>,
+        on_stack: BTreeSet<u32>,
+        components: Vec<Vec<u32>>,
+    }
+
+    fn visit(rules: &RuleSet, page: u32, state: &mut State) {
+        let next = state.index.len();
+        state.index.insert(page, next);
+        state.low.insert(page, next);
+        state.stack.push(page);
+        state.on_stack.insert(page);
+
+        for later in rules.successors(page).sorted() {
+            if !state.index.contains_key(&later) {
+                visit(rules, later, state);
+                let low = state.low[&page].min(state.low[&later]);
+                state.low.insert(page, low);
+            } else if state.on_stack.contains(&later) {
+                let low = state.low[&page].min(state.index[&later]);
+                state.low.insert(page, low);
+            }
+        }
+
+        if state.low[&page] == state.index[&page] {
+            let mut component = vec![];
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(&member);
+                component.push(member);
+                if member == page {
+                    break;
+                }
+            }
+            if component.len() > 1 || rules.must_precede(page, page) {
+                component.sort();
+                state.components.push(component);
+            }
+        }
+    }
+
+    let mut state = State {
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: vec![],
+        on_stack: BTreeSet::new(),
+        components: vec![],
+    };
+    for page in rules.pages().into_iter().sorted() {
+        if !state.index.contains_key(&page) {
+            visit(rules, page, &mut state);
+        }
+    }
+
+    state.components.sort();
+    state.components
+}
+
+// shortest chain from `from` to `to` of at least two rules, ignoring the direct one and
+// only going through pages which are `present`
+fn find_chain(
+    rules: &RuleSet,
+    from: u32,
+    to: u32,
+    present: impl Fn(u32) -> bool,
+) -> Option<Vec<u32>> {
+    let mut previous: HashMap<u32, u32> = HashMap::new();
+    let mut queue: VecDeque<u32> = rules
+        .successors(from)
+        .filter(|page| *page != to && present(*page))
+        .sorted()
+        .collect();
+    for page in queue.iter() {
+        previous.insert(*page, from);
+    }
+
+    while let Some(current) = queue.pop_front() {
+        for later in rules.successors(current).sorted() {
+            if previous.contains_key(&later) || later == from || !present(later) {
+                continue;
+            }
+            previous.insert(later, current);
+
+            if later == to {
+                let mut chain = vec![to];
+                let mut page = to;
+                while page != from {
+                    page = previous[&page];
+                    chain.push(page);
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            queue.push_back(later);
+        }
+    }
+
+    None
+}
+
+pub fn lint(rules: &[Rule]) -> Vec<Lint> {
+    let mut lints = vec![];
+
+    let mut lines: BTreeMap<Rule, Vec<usize>> = BTreeMap::new();
+    for (index, rule) in rules.iter().enumerate() {
+        lines.entry(*rule).or_default().push(index + 1);
+    }
+    for (rule, lines) in lines.iter() {
+        if lines.len() > 1 {
+            lints.push(Lint::Duplicate {
+                rule: *rule,
+                lines: lines.clone(),
+            });
+        }
+    }
+
+    for (a, b) in lines.keys() {
+        if a < b && lines.contains_key(&(*b, *a)) {
+            lints.push(Lint::Contradiction((*a, *b)));
+        }
+    }
+
+    let rule_set = RuleSet::from(rules.to_vec());
+    let loops = get_loops(&rule_set);
+    for component in loops.iter() {
+        // a lone contradiction has already been reported
+        if component.len() != 2 {
+            lints.push(Lint::Cycle(find_cycle(&rule_set, component)));
+        }
+    }
+
+    for rule in lines.keys() {
+        if let Some(via) = find_chain(&rule_set, rule.0, rule.1, |_| true) {
+            lints.push(Lint::Implied { rule: *rule, via });
+        }
+    }
+
+    lints
+}
+
+// drops duplicates, and any rule which every update it applies to already gets from a
+// chain of the other rules through that update's own pages, so `updates` sort and
+// validate exactly the same with what's left
+pub fn reduce(rules: &[Rule], updates: &[Update]) -> Vec<Rule> {
+    let mut rule_set = RuleSet::from(rules.to_vec());
+    let pages: Vec<HashSet<u32>> = updates
+        .iter()
+        .map(|update| update.iter().copied().collect())
+        .collect();
+
+    let mut reduced = vec![];
+    for rule in rules.iter().unique() {
+        let implied = find_chain(&rule_set, rule.0, rule.1, |_| true).is_some()
+            && pages
+                .iter()
+                .filter(|pages| pages.contains(&rule.0) && pages.contains(&rule.1))
+                .all(|pages| {
+                    find_chain(&rule_set, rule.0, rule.1, |page| pages.contains(&page)).is_some()
+                });
+        match rule_set.after.get_mut(&rule.0) {
+            Some(after) if implied => {
+                after.remove(&rule.1);
+            }
+            _ => reduced.push(*rule),
+        }
+    }
+    reduced
+}
+
+// in the same `a|b` format as the input
+pub fn format_rules(rules: &[Rule]) -> String {
+    rules
+        .iter()
+        .map(|(a, b)| format!("{}|{}\n", a, b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        get_rules, get_values,
+        lint::{format_rules, lint, reduce, Lint},
+        parse_rules, parse_updates, sort_values,
+    };
+
+    #[test]
+    fn should_find_duplicates_and_contradictions() {
+        let rules = parse_rules("1|2\n3|4\n1|2\n4|3\n1|2").unwrap();
+        assert_eq!(
+            lint(&rules),
+            vec![
+                Lint::Duplicate {
+                    rule: (1, 2),
+                    lines: vec![1, 3, 5]
+                },
+                Lint::Contradiction((3, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_find_cycles() {
+        let rules = parse_rules("1|2\n2|3\n3|1\n5|5\n3|4").unwrap();
+        assert_eq!(
+            lint(&rules),
+            vec![Lint::Cycle(vec![1, 2, 3]), Lint::Cycle(vec![5])]
+        );
+        assert_eq!(
+            lint(&rules)[0].to_string(),
+            "rules form a cycle: 1|2, 2|3, 3|1"
+        );
+    }
+
+    #[test]
+    fn should_find_implied_rules() {
+        let rules = parse_rules("1|2\n2|3\n1|3\n3|4\n1|4\n2|4").unwrap();
+        let lints = lint(&rules);
+        assert_eq!(
+            lints,
+            vec![
+                Lint::Implied {
+                    rule: (1, 3),
+                    via: vec![1, 2, 3]
+                },
+                Lint::Implied {
+                    rule: (1, 4),
+                    via: vec![1, 2, 4]
+                },
+                Lint::Implied {
+                    rule: (2, 4),
+                    via: vec![2, 3, 4]
+                },
+            ]
+        );
+        assert_eq!(
+            lints[0].to_string(),
+            "rule 1|3 is implied by 1|2|3 when those pages are present"
+        );
+    }
+
+    #[test]
+    fn should_reduce_to_minimal_rules() {
+        let rules = parse_rules("1|2\n2|3\n1|3\n3|4\n1|4\n2|4\n1|2\n5|4").unwrap();
+        let updates = parse_updates("1,2,3,4\n4,3,2,1,5").unwrap();
+        let reduced = reduce(&rules, &updates);
+        assert_eq!(reduced, vec![(1, 2), (2, 3), (3, 4), (5, 4)]);
+        assert_eq!(format_rules(&reduced), "1|2\n2|3\n3|4\n5|4\n");
+    }
+
+    #[test]
+    fn should_keep_rules_whose_chain_is_missing_from_an_update() {
+        let rules = parse_rules("1|2\n2|3\n1|3").unwrap();
+        // 3,1 doesn't have 2, so only 1|3 says it's wrong
+        let updates = "3,1\n1,2,3";
+        let reduced = reduce(&rules, &parse_updates(updates).unwrap());
+        assert_eq!(reduced, rules);
+
+        let updates = "2,1,3\n1,2,3";
+        let reduced = reduce(&rules, &parse_updates(updates).unwrap());
+        assert_eq!(reduced, vec![(1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn reduced_rules_should_leave_updates_unchanged() {
+        // cyclic as a whole, like real puzzle input, but not within any one update
+        let rules = "1|2\n2|3\n1|3\n3|4\n2|4\n4|1\n1|2";
+        let updates = "3,1\n1,2,3\n3,2,1\n2,3,4\n4,3,2\n1,3";
+        let reduced = reduce(
+            &parse_rules(rules).unwrap(),
+            &parse_updates(updates).unwrap(),
+        );
+        // 2|4 always has 3 in between, 1|3 doesn't have 2 in 3,1 or 1,3
+        assert_eq!(reduced, vec![(1, 2), (2, 3), (1, 3), (3, 4), (4, 1)]);
+
+        let before = get_values(updates, &get_rules(rules).unwrap()).unwrap();
+        let after = get_values(updates, &get_rules(&format_rules(&reduced)).unwrap()).unwrap();
+        assert_eq!(after, before);
+        assert_eq!(
+            sort_values(&get_rules(&format_rules(&reduced)).unwrap(), after.1).unwrap(),
+            sort_values(&get_rules(rules).unwrap(), before.1).unwrap()
+        );
+    }
+}
@@ -1,110 +1,395 @@
-use std::{cmp::Ordering, fs::read_to_string, io};
-
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    env::args,
+    fmt::Display,
+    fs::{read_to_string, write},
+    io,
+    iter::repeat_n,
+    ops::Range,
+};
+
+use aggregate::{AggregateError, Aggregator};
+use explain::explain;
 use itertools::Itertools;
+use lint::{format_rules, lint, reduce};
+
+mod aggregate;
+mod explain;
+mod lint;
 
 fn main() -> Result<(), io::Error> {
+    let args: Vec<String> = args().collect();
     let input = read_to_string("./input.txt")?;
-    let (rules, values) = input.split("\n\n").collect_tuple().unwrap();
+    let (rules, values) = split_sections(&input)?;
+
+    if args.iter().any(|arg| arg == "--lint") {
+        for problem in lint(&parse_rules(&rules)?) {
+            println!("{}", problem);
+        }
+    }
 
-    let rules = get_rules(rules);
-    let (valid, invalid) = get_values(values, &rules);
+    // write out a smaller set of rules which treats these updates the same
+    if let Some(index) = args.iter().position(|arg| arg == "--reduce") {
+        let filename = args
+            .get(index + 1)
+            .expect("--reduce needs a file to write to");
+        let reduced = reduce(&parse_rules(&rules)?, &parse_updates(&values)?);
+        write(filename, format_rules(&reduced))?;
+    }
+
+    // what to take from each update, the middle page by default
+    let aggregator = match args.iter().position(|arg| arg == "--aggregate") {
+        Some(index) => args
+            .get(index + 1)
+            .expect("--aggregate needs a name")
+            .parse::<Aggregator>()?,
+        None => Aggregator::default(),
+    };
+
+    let rules = get_rules(&rules)?;
+    let (valid, invalid) = get_values(&values, &rules)?;
+
+    // why each invalid update is wrong, and how to fix it
+    if args.iter().any(|arg| arg == "--explain") {
+        for update in invalid.iter() {
+            println!("{}", explain(&rules, update)?);
+        }
+    }
 
     // part 1
-    let part_one_total = get_total(&valid);
+    let part_one_total = get_total(&valid, aggregator)?;
     println!("Part 1 total: {}", part_one_total);
 
     // part 2
     // sort
-    let sorted = sort_values(&rules, invalid);
+    let sorted = sort_values(&rules, invalid)?;
+    for update in sorted.iter().filter(|update| update.ambiguous) {
+        println!("More than one valid order for {:?}", update.pages);
+    }
     // get middle
     // sum
-    let part_two_total = get_total(&sorted);
+    let sorted: Vec<Vec<u32>> = sorted.into_iter().map(|update| update.pages).collect();
+    let part_two_total = get_total(&sorted, aggregator)?;
     println!("Part 2 total: {:?}", part_two_total);
 
     Ok(())
 }
 
-fn sort_values(rules: &Vec<Rule>, invalid: Vec<Vec<u32>>) -> Vec<Vec<u32>> {
-    let sorted: Vec<Vec<u32>> = invalid
-        .into_iter()
-        .map(|mut value| {
-            value.sort_by(|a, b| {
-                if rules.contains(&(*a, *b)) {
-                    Ordering::Less
-                } else if rules.contains(&(*b, *a)) {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
+#[derive(Debug, PartialEq)]
+enum OrderError {
+    // each page has to come before the next, and the last one before the first
+    Cycle(Vec<u32>),
+}
+
+impl Display for OrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderError::Cycle(pages) => {
+                let chain = pages
+                    .iter()
+                    .zip(pages.iter().cycle().skip(1))
+                    .map(|(a, b)| format!("{}|{}", a, b))
+                    .join(", ");
+                write!(f, "rules form a cycle: {}", chain)
+            }
+        }
+    }
+}
+
+impl From<OrderError> for io::Error {
+    fn from(err: OrderError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SortedUpdate {
+    pages: Vec<u32>,
+    // the rules don't pin down a single order, so the middle page might not be reliable
+    ambiguous: bool,
+}
+
+// kahn's algorithm over just the pages in the update
+fn sort_update(rules: &RuleSet, update: &[u32]) -> Result<SortedUpdate, OrderError> {
+    // distinct pages in the order they first appear, and how many times each one does
+    let mut pages: Vec<u32> = vec![];
+    let mut counts: Vec<usize> = vec![];
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    for page in update {
+        match index.get(page) {
+            Some(i) => counts[*i] += 1,
+            None => {
+                index.insert(*page, pages.len());
+                pages.push(*page);
+                counts.push(1);
+            }
+        }
+    }
+
+    let mut in_degree = vec![0; pages.len()];
+    for page in pages.iter() {
+        for later in rules.successors(*page) {
+            if let Some(i) = index.get(&later) {
+                in_degree[*i] += 1;
+            }
+        }
+    }
+
+    // ties go to whichever page came first in the update
+    let mut ready: BTreeSet<usize> = (0..pages.len()).filter(|i| in_degree[*i] == 0).collect();
+    let mut sorted = vec![];
+    let mut ambiguous = false;
+
+    while let Some(current) = ready.pop_first() {
+        if !ready.is_empty() {
+            ambiguous = true;
+        }
+        sorted.extend(repeat_n(pages[current], counts[current]));
+
+        for later in rules.successors(pages[current]) {
+            if let Some(i) = index.get(&later) {
+                in_degree[*i] -= 1;
+                if in_degree[*i] == 0 {
+                    ready.insert(*i);
                 }
-            });
-            value
-        })
-        .collect();
+            }
+        }
+    }
 
-    sorted
+    if sorted.len() < update.len() {
+        let stuck: Vec<u32> = (0..pages.len())
+            .filter(|i| in_degree[*i] > 0)
+            .map(|i| pages[i])
+            .collect();
+        return Err(OrderError::Cycle(find_cycle(rules, &stuck)));
+    }
+
+    Ok(SortedUpdate {
+        pages: sorted,
+        ambiguous,
+    })
+}
+
+// every stuck page has a stuck page before it, so walking backwards has to loop
+fn find_cycle(rules: &RuleSet, stuck: &[u32]) -> Vec<u32> {
+    let mut path = vec![stuck[0]];
+    loop {
+        let current = path[path.len() - 1];
+        let previous = *stuck
+            .iter()
+            .find(|page| rules.must_precede(**page, current))
+            .expect("Stuck page must have a stuck predecessor");
+
+        if let Some(start) = path.iter().position(|page| *page == previous) {
+            let mut cycle = path[start..].to_vec();
+            cycle.reverse();
+            // start from the lowest page so the same cycle always reads the same
+            let lowest = cycle.iter().position_min().unwrap();
+            cycle.rotate_left(lowest);
+            return cycle;
+        }
+        path.push(previous);
+    }
 }
 
-fn get_total(values: &Vec<Vec<u32>>) -> u32 {
-    values.iter().map(|v| get_middle(v)).sum()
+fn sort_values(rules: &RuleSet, invalid: Vec<Vec<u32>>) -> Result<Vec<SortedUpdate>, OrderError> {
+    invalid
+        .iter()
+        .map(|value| sort_update(rules, value))
+        .collect()
 }
 
-fn check_rules(rules: &Vec<Rule>, values: &Vec<u32>) -> bool {
-    rules.iter().all(|rule| is_valid(rule, &values))
+fn get_total(values: &[Vec<u32>], aggregator: Aggregator) -> Result<u64, AggregateError> {
+    values.iter().try_fold(0u64, |total, update| {
+        total
+            .checked_add(aggregator.apply(update)?)
+            .ok_or(AggregateError::Overflow)
+    })
 }
 
 type Rule = (u32, u32);
 
-fn is_valid(rule: &Rule, update: &Vec<u32>) -> bool {
-    let both_present = update.contains(&rule.0) && update.contains(&rule.1);
-    if !both_present {
-        return true;
+// rules indexed by the page which has to come first
+#[derive(Debug, PartialEq, Default)]
+struct RuleSet {
+    after: HashMap<u32, HashSet<u32>>,
+}
+
+impl From<Vec<Rule>> for RuleSet {
+    fn from(rules: Vec<Rule>) -> Self {
+        let mut rule_set = RuleSet::default();
+        for (before, after) in rules {
+            rule_set.after.entry(before).or_default().insert(after);
+        }
+        rule_set
+    }
+}
+
+impl RuleSet {
+    fn pages(&self) -> HashSet<u32> {
+        self.after
+            .iter()
+            .flat_map(|(before, after)| after.iter().chain([before]))
+            .copied()
+            .collect()
     }
 
-    let first_pos = update.iter().position(|i| i == &rule.0);
-    let second_pos = update.iter().position(|i| i == &rule.1);
+    fn successors(&self, page: u32) -> impl Iterator<Item = u32> + '_ {
+        self.after.get(&page).into_iter().flatten().copied()
+    }
 
-    match (first_pos, second_pos) {
-        (None, None) => true,
-        (Some(first), Some(second)) => second > first,
-        _ => true,
+    fn must_precede(&self, a: u32, b: u32) -> bool {
+        self.after.get(&a).is_some_and(|after| after.contains(&b))
+    }
+
+    // rules only apply when both pages are in the update
+    fn is_valid(&self, update: &[u32]) -> bool {
+        let mut positions: HashMap<u32, usize> = HashMap::new();
+        for (index, page) in update.iter().enumerate() {
+            positions.entry(*page).or_insert(index);
+        }
+
+        positions.iter().all(|(page, position)| {
+            self.after.get(page).is_none_or(|after| {
+                after
+                    .iter()
+                    .all(|later| positions.get(later).is_none_or(|p| p > position))
+            })
+        })
     }
 }
 
-fn get_middle(values: &Vec<u32>) -> u32 {
-    values[values.len() / 2]
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    MissingRules,
+    MissingUpdates,
+    InvalidRule(String),
+    InvalidUpdate(String),
 }
 
-fn get_values(input: &str, rules: &Vec<Rule>) -> (Vec<Vec<u32>>, Vec<Vec<u32>>) {
-    input
-        .lines()
-        .map(|line| {
-            line.trim()
-                .split(",")
-                .map(|v| v.parse::<u32>().unwrap())
-                .collect()
-        })
-        .partition(|v| check_rules(&rules, v))
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MissingRules => write!(f, "no rules found"),
+            ParseError::MissingUpdates => write!(f, "no updates found"),
+            ParseError::InvalidRule(line) => write!(f, "invalid rule: {}", line),
+            ParseError::InvalidUpdate(line) => write!(f, "invalid update: {}", line),
+        }
+    }
 }
 
-fn get_rules(input: &str) -> Vec<Rule> {
-    input
-        .lines()
-        .map(|line| {
-            line.trim()
-                .split("|")
-                .map(|rule| rule.parse::<u32>().unwrap())
-                .collect_tuple()
-                .unwrap()
-        })
-        .collect()
+impl From<ParseError> for io::Error {
+    fn from(err: ParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+    }
+}
+
+// all mean the left page comes first
+const RULE_SEPARATORS: [&str; 3] = ["|", "->", "<"];
+
+// without a `#` comment or surrounding whitespace
+fn clean(line: &str) -> &str {
+    line.split('#').next().unwrap_or("").trim()
+}
+
+// without `#` comments, blank lines or surrounding whitespace. `lines` also drops the `\r`
+// from crlf endings
+fn clean_lines(input: &str) -> impl Iterator<Item = &str> {
+    input.lines().map(clean).filter(|line| !line.is_empty())
+}
+
+fn is_rule(line: &str) -> bool {
+    RULE_SEPARATORS
+        .iter()
+        .any(|separator| line.contains(separator))
+}
+
+// rules come first and updates after. The first blank line after the rules splits them, so
+// a mistyped rule is still reported as a rule. Without one, the rules end at the first line
+// that doesn't look like one
+fn split_sections(input: &str) -> Result<(String, String), ParseError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let cleaned: Vec<&str> = lines.iter().map(|line| clean(line)).collect();
+    let boundary = cleaned
+        .iter()
+        .position(|line| !line.is_empty())
+        .and_then(|first| (first..lines.len()).find(|index| lines[*index].trim().is_empty()))
+        .filter(|boundary| cleaned[*boundary..].iter().any(|line| !line.is_empty()));
+
+    let section = |range: Range<usize>| -> Vec<&str> {
+        cleaned[range]
+            .iter()
+            .filter(|line| !line.is_empty())
+            .copied()
+            .collect()
+    };
+    let (rules, updates) = match boundary {
+        Some(boundary) => (section(0..boundary), section(boundary..lines.len())),
+        None => {
+            let mut lines = clean_lines(input).peekable();
+            let mut rules = vec![];
+            while let Some(line) = lines.next_if(|line| is_rule(line)) {
+                rules.push(line);
+            }
+            (rules, lines.collect())
+        }
+    };
+
+    if rules.is_empty() {
+        return Err(ParseError::MissingRules);
+    }
+    if updates.is_empty() {
+        return Err(ParseError::MissingUpdates);
+    }
+
+    Ok((rules.join("\n"), updates.join("\n")))
+}
+
+fn parse_update(line: &str) -> Result<Vec<u32>, ParseError> {
+    line.split(",")
+        .map(|v| v.trim().parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| ParseError::InvalidUpdate(line.to_string()))
+}
+
+type Update = Vec<u32>;
+
+fn parse_updates(input: &str) -> Result<Vec<Update>, ParseError> {
+    clean_lines(input).map(parse_update).collect()
+}
+
+// (valid, invalid)
+fn get_values(input: &str, rules: &RuleSet) -> Result<(Vec<Update>, Vec<Update>), ParseError> {
+    let updates = parse_updates(input)?;
+
+    Ok(updates.into_iter().partition(|v| rules.is_valid(v)))
+}
+
+fn parse_rule(line: &str) -> Result<Rule, ParseError> {
+    RULE_SEPARATORS
+        .iter()
+        .find_map(|separator| line.split_once(separator))
+        .and_then(|(before, after)| Some((before.trim().parse().ok()?, after.trim().parse().ok()?)))
+        .ok_or_else(|| ParseError::InvalidRule(line.to_string()))
+}
+
+fn parse_rules(input: &str) -> Result<Vec<Rule>, ParseError> {
+    clean_lines(input).map(parse_rule).collect()
+}
+
+fn get_rules(input: &str) -> Result<RuleSet, ParseError> {
+    Ok(RuleSet::from(parse_rules(input)?))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{get_total, get_values, sort_values};
+    use crate::{
+        aggregate::{AggregateError, Aggregator},
+        get_total, get_values, sort_values, RuleSet,
+    };
 
     mod is_valid {
-        use crate::is_valid;
+        use crate::RuleSet;
 
         #[test]
         fn is_valid_if_in_correct_order() {
@@ -112,31 +397,31 @@ mod tests {
             let update = vec![75, 47, 61, 53, 29];
 
             // follows rule
-            assert_eq!(is_valid(&rule, &update), true);
+            assert!(RuleSet::from(vec![rule]).is_valid(&update));
         }
 
         #[test]
         fn is_invalid_if_in_incorrect_order() {
             let rule = (47, 53);
-            assert_eq!(is_valid(&rule, &vec![75, 53, 61, 47, 29]), false);
+            assert!(!RuleSet::from(vec![rule]).is_valid(&[75, 53, 61, 47, 29]));
         }
 
         #[test]
         fn is_valid_if_both_missing() {
             let rule = (47, 53);
-            assert_eq!(is_valid(&rule, &vec![75, 54, 61, 48, 28]), true);
+            assert!(RuleSet::from(vec![rule]).is_valid(&[75, 54, 61, 48, 28]));
         }
 
         #[test]
         fn is_valid_if_one_missing() {
             let rule = (47, 55);
-            assert_eq!(is_valid(&rule, &vec![75, 53, 61, 48, 28]), true);
+            assert!(RuleSet::from(vec![rule]).is_valid(&[75, 53, 61, 48, 28]));
         }
     }
 
     #[test]
     fn should_parse_values() {
-        let rules = vec![
+        let rules = RuleSet::from(vec![
             (75, 29),
             (61, 13),
             (75, 53),
@@ -154,7 +439,7 @@ mod tests {
             (47, 29),
             (75, 13),
             (53, 13),
-        ];
+        ]);
         let input = "69,32,62,98,65,72,59,15,56,89,87
 64,35,16,77,86,75,91,26,49,69,89,15,99,59,29,42,56,97,38
 26,53,16,35,49,99,86,69,89,15,77,83,42,56,72,47,94,65,59,38,75,29,91
@@ -266,17 +551,17 @@ mod tests {
             ],
         ];
         assert_eq!(
-            get_values(&input, &rules),
+            get_values(input, &rules).unwrap(),
             (expected_valid, expected_invalid)
         );
     }
 
     mod rules {
-        use crate::{check_rules, get_rules};
+        use crate::{get_rules, RuleSet};
 
         #[test]
         fn is_valid_with_multiple_rules() {
-            let rules = vec![
+            let rules = RuleSet::from(vec![
                 (75, 29),
                 (61, 13),
                 (75, 53),
@@ -294,15 +579,15 @@ mod tests {
                 (47, 29),
                 (75, 13),
                 (53, 13),
-            ];
+            ]);
             let values = vec![75, 47, 61, 53, 29];
 
-            assert_eq!(check_rules(&rules, &values), true);
+            assert!(rules.is_valid(&values));
         }
 
         #[test]
         fn is_invalid_if_in_incorrect_order_multiple_rules() {
-            let rules = vec![
+            let rules = RuleSet::from(vec![
                 (75, 29),
                 (61, 13),
                 (75, 53),
@@ -320,12 +605,22 @@ mod tests {
                 (47, 29),
                 (75, 13),
                 (53, 13),
-            ];
+            ]);
             let values = vec![47, 75, 47, 61, 53, 29];
-            assert_eq!(check_rules(&rules, &values), false);
+            assert!(!rules.is_valid(&values));
 
             let update = vec![97, 13, 75, 29, 47];
-            assert_eq!(check_rules(&rules, &update), false);
+            assert!(!rules.is_valid(&update));
+        }
+
+        #[test]
+        fn should_only_look_up_rule_forwards() {
+            let rules = get_rules("47|53\n97|13\n47|13").unwrap();
+
+            assert!(rules.must_precede(47, 53));
+            assert!(rules.must_precede(47, 13));
+            assert!(!rules.must_precede(53, 47));
+            assert!(!rules.must_precede(97, 53));
         }
 
         #[test]
@@ -348,8 +643,8 @@ mod tests {
               75|13
               53|13";
             assert_eq!(
-                get_rules(&input),
-                vec![
+                get_rules(input).unwrap(),
+                RuleSet::from(vec![
                     (75, 29),
                     (61, 13),
                     (75, 53),
@@ -367,7 +662,7 @@ mod tests {
                     (47, 29),
                     (75, 13),
                     (53, 13)
-                ]
+                ])
             );
         }
     }
@@ -380,12 +675,21 @@ mod tests {
             vec![75, 29, 13],
         ];
 
-        assert_eq!(get_total(&values), 143);
+        assert_eq!(get_total(&values, Aggregator::default()), Ok(143));
+    }
+
+    #[test]
+    fn should_not_total_empty_update() {
+        let values = vec![vec![75, 47, 61], vec![]];
+        assert_eq!(
+            get_total(&values, Aggregator::default()),
+            Err(AggregateError::Empty)
+        );
     }
 
     #[test]
     fn it_should_sort_based_on_rules() {
-        let rules = vec![
+        let rules = RuleSet::from(vec![
             (75, 29),
             (61, 13),
             (75, 53),
@@ -403,15 +707,20 @@ mod tests {
             (47, 29),
             (75, 13),
             (53, 13),
-        ];
+        ]);
         let values = vec![
             vec![75, 97, 47, 61, 53],
             vec![61, 13, 29],
             vec![97, 13, 75, 29, 47],
         ];
 
+        let sorted: Vec<Vec<u32>> = sort_values(&rules, values)
+            .unwrap()
+            .into_iter()
+            .map(|update| update.pages)
+            .collect();
         assert_eq!(
-            sort_values(&rules, values),
+            sorted,
             vec![
                 vec![97, 75, 47, 61, 53],
                 vec![61, 29, 13],
@@ -419,4 +728,121 @@ mod tests {
             ]
         );
     }
+
+    mod sort {
+        use crate::{get_rules, sort_update, OrderError, SortedUpdate};
+
+        #[test]
+        fn should_flag_ambiguous_order() {
+            let rules = get_rules("1|2\n1|3").unwrap();
+            assert_eq!(
+                sort_update(&rules, &[3, 2, 1]),
+                Ok(SortedUpdate {
+                    pages: vec![1, 3, 2],
+                    ambiguous: true
+                })
+            );
+
+            let rules = get_rules("1|2\n1|3\n2|3").unwrap();
+            assert_eq!(
+                sort_update(&rules, &[3, 2, 1]),
+                Ok(SortedUpdate {
+                    pages: vec![1, 2, 3],
+                    ambiguous: false
+                })
+            );
+        }
+
+        #[test]
+        fn should_keep_repeated_pages_together() {
+            let rules = get_rules("1|2").unwrap();
+            assert_eq!(
+                sort_update(&rules, &[2, 1, 2]).unwrap().pages,
+                vec![1, 2, 2]
+            );
+        }
+
+        #[test]
+        fn should_report_cycle() {
+            // 4 is stuck behind the cycle but isn't part of it
+            let rules = get_rules("1|2\n2|3\n3|1\n3|4\n5|1").unwrap();
+            let err = sort_update(&rules, &[4, 1, 2, 3, 5]).unwrap_err();
+            assert_eq!(err, OrderError::Cycle(vec![1, 2, 3]));
+            assert_eq!(err.to_string(), "rules form a cycle: 1|2, 2|3, 3|1");
+        }
+
+        #[test]
+        fn should_ignore_cycles_outside_the_update() {
+            let rules = get_rules("1|2\n2|3\n3|1").unwrap();
+            assert_eq!(sort_update(&rules, &[2, 1]).unwrap().pages, vec![1, 2]);
+        }
+    }
+
+    mod parse {
+        use crate::{get_rules, get_values, parse_rules, split_sections, ParseError, RuleSet};
+
+        #[test]
+        fn should_split_sections() {
+            let input = "# ordering rules\r\n47|53\r\n97|13 # trailing comment\r\n\r\n\r\n  \r\n75,47,61\r\n\r\n97,13\r\n\r\n";
+            assert_eq!(
+                split_sections(input),
+                Ok(("47|53\n97|13".to_string(), "75,47,61\n97,13".to_string()))
+            );
+
+            // no blank line at all, or only at the end
+            assert_eq!(
+                split_sections("1|2\n1,2"),
+                Ok(("1|2".to_string(), "1,2".to_string()))
+            );
+            assert_eq!(
+                split_sections("1|2\n1,2\n\n"),
+                Ok(("1|2".to_string(), "1,2".to_string()))
+            );
+        }
+
+        #[test]
+        fn should_report_missing_sections() {
+            assert_eq!(
+                split_sections("# nothing\n\n75,47,61"),
+                Err(ParseError::MissingRules)
+            );
+            assert_eq!(
+                split_sections("47|53\n\n# no updates yet\n"),
+                Err(ParseError::MissingUpdates)
+            );
+            assert_eq!(split_sections(""), Err(ParseError::MissingRules));
+        }
+
+        #[test]
+        fn should_accept_other_separators() {
+            assert_eq!(
+                parse_rules("47|53\n97 -> 13\n75<29"),
+                Ok(vec![(47, 53), (97, 13), (75, 29)])
+            );
+        }
+
+        #[test]
+        fn should_report_invalid_lines() {
+            assert_eq!(
+                parse_rules("47|53\n97|x"),
+                Err(ParseError::InvalidRule("97|x".to_string()))
+            );
+            assert_eq!(
+                parse_rules("47|53|61"),
+                Err(ParseError::InvalidRule("47|53|61".to_string()))
+            );
+            // above the blank line, so still a rule even without a separator
+            for typo in ["47-53", "61:13"] {
+                let (rules, _) = split_sections(&format!("47|53\n{}\n\n75,47", typo)).unwrap();
+                assert_eq!(
+                    get_rules(&rules),
+                    Err(ParseError::InvalidRule(typo.to_string()))
+                );
+            }
+            assert_eq!(
+                get_values("1,2\n3,,4", &RuleSet::default()),
+                Err(ParseError::InvalidUpdate("3,,4".to_string()))
+            );
+        }
+    }
 }
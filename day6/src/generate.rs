@@ -0,0 +1,279 @@
+use std::{io, str::FromStr};
+
+use crate::{
+    jump::Jumps,
+    render::{glyph, EMPTY, OBSTACLE},
+    Direction, Map, Position,
+};
+
+// how many maps to make before giving up on one the guard gets out of (or doesn't)
+const ATTEMPTS: usize = 1000;
+
+// splitmix64, so the same seed always makes the same map
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    // true with probability `p`
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Ensure {
+    Exit,
+    Loop,
+}
+
+impl FromStr for Ensure {
+    type Err = io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "exit" => Ok(Ensure::Exit),
+            "loop" => Ok(Ensure::Loop),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown patrol {}, expected exit or loop", name),
+            )),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Settings {
+    pub width: usize,
+    pub height: usize,
+    // chance of each cell being an obstacle
+    pub density: f64,
+    // somewhere random if not given
+    pub guard: Option<Position>,
+    pub facing: Direction,
+    // keep trying until the puzzle's guard gets out, or goes round forever
+    pub ensure: Option<Ensure>,
+    pub seed: u64,
+}
+
+impl Default for Settings {
+    // about the size of the puzzle input
+    fn default() -> Self {
+        Settings {
+            width: 130,
+            height: 130,
+            density: 0.015,
+            guard: None,
+            facing: Direction::North,
+            ensure: None,
+            seed: 0,
+        }
+    }
+}
+
+impl Settings {
+    fn validate(&self) -> Result<(), io::Error> {
+        let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        if self.width == 0 || self.height == 0 {
+            return invalid(format!("map can't be {}x{}", self.width, self.height));
+        }
+        if !(0.0..=1.0).contains(&self.density) {
+            return invalid(format!(
+                "density {} should be between 0 and 1",
+                self.density
+            ));
+        }
+        match self.guard {
+            Some((x, y)) if x >= self.width || y >= self.height => invalid(format!(
+                "guard at {},{} is off a {}x{} map",
+                x, y, self.width, self.height
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn make(settings: &Settings, rng: &mut Rng) -> String {
+    let guard = settings
+        .guard
+        .unwrap_or_else(|| (rng.below(settings.width), rng.below(settings.height)));
+
+    (0..settings.height)
+        .map(|y| {
+            (0..settings.width)
+                .map(|x| {
+                    // always roll, so the guard doesn't change the rest of the map
+                    let blocked = rng.chance(settings.density);
+                    if (x, y) == guard {
+                        glyph(settings.facing)
+                    } else if blocked {
+                        OBSTACLE
+                    } else {
+                        EMPTY
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// a map in the same format as the puzzle input, or None if nothing in `ATTEMPTS` tries had
+// the patrol asked for. Errors if the settings can't make a map at all
+pub fn generate(settings: &Settings) -> Result<Option<String>, io::Error> {
+    settings.validate()?;
+    let mut rng = Rng::new(settings.seed);
+
+    Ok((0..ATTEMPTS)
+        .map(|_| make(settings, &mut rng))
+        .find(|text| {
+            let map = Map::parse(text);
+            match settings.ensure {
+                None => true,
+                Some(Ensure::Exit) => !Jumps::new(&map).walk(map.start).looped,
+                Some(Ensure::Loop) => Jumps::new(&map).walk(map.start).looped,
+            }
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generate::{generate, Ensure, Settings},
+        run, Direction, Map,
+    };
+
+    #[test]
+    fn should_be_same_for_same_seed() {
+        let settings = Settings {
+            width: 20,
+            height: 10,
+            seed: 4,
+            ..Settings::default()
+        };
+        let text = generate(&settings).unwrap().unwrap();
+
+        assert_eq!(text, generate(&settings).unwrap().unwrap());
+        assert_ne!(
+            text,
+            generate(&Settings {
+                seed: 5,
+                ..settings
+            })
+            .unwrap()
+            .unwrap()
+        );
+
+        let map = Map::parse(&text);
+        assert_eq!((map.width, map.height), (20, 10));
+        assert_eq!(map.guards.len(), 1);
+    }
+
+    #[test]
+    fn should_place_guard() {
+        let text = generate(&Settings {
+            width: 5,
+            height: 5,
+            density: 1.0,
+            guard: Some((1, 3)),
+            facing: Direction::West,
+            ..Settings::default()
+        })
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(text, "#####\n#####\n#####\n#<###\n#####");
+    }
+
+    #[test]
+    fn should_ensure_patrol() {
+        let settings = Settings {
+            width: 12,
+            height: 12,
+            density: 0.1,
+            seed: 1,
+            ..Settings::default()
+        };
+
+        for (ensure, exits) in [(Ensure::Exit, true), (Ensure::Loop, false)] {
+            let text = generate(&Settings {
+                ensure: Some(ensure),
+                ..settings
+            })
+            .unwrap()
+            .unwrap();
+            let mut map = Map::parse(&text);
+            assert_eq!(run(&mut map).is_some(), exits);
+        }
+
+        // boxed in, so never gets out
+        let boxed = Settings {
+            density: 1.0,
+            guard: Some((5, 5)),
+            ensure: Some(Ensure::Exit),
+            ..settings
+        };
+        assert_eq!(generate(&boxed).unwrap(), None);
+    }
+
+    #[test]
+    fn should_reject_bad_settings() {
+        let settings = Settings {
+            width: 4,
+            height: 3,
+            ..Settings::default()
+        };
+
+        for bad in [
+            Settings {
+                width: 0,
+                ..settings
+            },
+            Settings {
+                height: 0,
+                ..settings
+            },
+            Settings {
+                guard: Some((9, 9)),
+                ..settings
+            },
+            Settings {
+                guard: Some((4, 0)),
+                ..settings
+            },
+            Settings {
+                density: 1.5,
+                ..settings
+            },
+            Settings {
+                density: -0.1,
+                ..settings
+            },
+        ] {
+            assert!(generate(&bad).is_err(), "{:?}", bad);
+        }
+
+        let corner = generate(&Settings {
+            guard: Some((3, 2)),
+            density: 0.0,
+            ..settings
+        });
+        assert_eq!(corner.unwrap().unwrap(), "....\n....\n...^");
+    }
+}
@@ -0,0 +1,379 @@
+use std::{collections::HashSet, fmt::Display, io, str::FromStr};
+
+use crate::{jump::BitSet, state, Direction, Guard, Map, StepOutcome};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Turn {
+    Right,
+    Left,
+    Reverse,
+}
+
+impl FromStr for Turn {
+    type Err = io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "right" => Ok(Turn::Right),
+            "left" => Ok(Turn::Left),
+            "reverse" => Ok(Turn::Reverse),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown turn {}, expected right, left or reverse", name),
+            )),
+        }
+    }
+}
+
+// how every guard moves. Diagonal guards go half a turn clockwise of the way they face, so
+// `^` heads north east, `>` south east and so on
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Rules {
+    pub turn: Turn,
+    pub diagonal: bool,
+}
+
+impl Default for Rules {
+    // the puzzle's
+    fn default() -> Self {
+        Rules {
+            turn: Turn::Right,
+            diagonal: false,
+        }
+    }
+}
+
+impl Rules {
+    pub fn delta(&self, facing: Direction) -> (isize, isize) {
+        let (dx, dy) = facing.delta();
+        if self.diagonal {
+            let (cx, cy) = facing.clockwise().delta();
+            (dx + cx, dy + cy)
+        } else {
+            (dx, dy)
+        }
+    }
+
+    pub fn turn(&self, facing: Direction) -> Direction {
+        match self.turn {
+            Turn::Right => facing.clockwise(),
+            Turn::Left => facing.clockwise().clockwise().clockwise(),
+            Turn::Reverse => facing.clockwise().clockwise(),
+        }
+    }
+}
+
+// what happened to a guard, and after how many steps
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Fate {
+    Exited { steps: usize },
+    // ran into other guards, by index, possibly more than one at once. They all stop there
+    Collided { with: Vec<usize>, steps: usize },
+    // first got back to somewhere it had already been facing the same way
+    Looped { steps: usize },
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct Lockstep {
+    pub fates: Vec<Fate>,
+    // every guard still walking was going round forever together
+    pub looped: bool,
+    pub steps: usize,
+}
+
+impl Display for Lockstep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, fate) in self.fates.iter().enumerate() {
+            match fate {
+                Fate::Exited { steps } => {
+                    writeln!(f, "guard {} left after {} steps", index, steps)?
+                }
+                Fate::Collided { with, steps } => writeln!(
+                    f,
+                    "guard {} ran into guard {} after {} steps",
+                    index,
+                    with.iter()
+                        .map(|other| other.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" and "),
+                    steps
+                )?,
+                Fate::Looped { steps } => {
+                    writeln!(f, "guard {} is in a loop after {} steps", index, steps)?
+                }
+            }
+        }
+        match self.looped {
+            true => write!(f, "patrol loops forever after {} steps", self.steps),
+            false => write!(f, "patrol over after {} steps", self.steps),
+        }
+    }
+}
+
+// every guard on the map takes a step at the same time. Guards don't block each other, but
+// end up on the same cell or walk through or across each other and they stop, and anyone walking
+// onto a guard that's already stopped stops as well. A guard going round in
+// a loop can still be run into, so it only counts as looped once everyone left is looping
+// and they're all back where they were together
+pub fn lockstep(map: &Map) -> Lockstep {
+    let mut guards = map.guards.clone();
+    let mut fates: Vec<Option<Fate>> = vec![None; guards.len()];
+    let mut looped_at: Vec<Option<usize>> = vec![None; guards.len()];
+    let mut seen = vec![BitSet::new(map.width * map.height * 4); guards.len()];
+    let mut together: HashSet<Vec<Guard>> = HashSet::new();
+    let mut steps = 0;
+
+    loop {
+        let active: Vec<usize> = (0..guards.len())
+            .filter(|index| fates[*index].is_none())
+            .collect();
+        if active.is_empty() {
+            break;
+        }
+
+        for index in active.iter() {
+            let guard = &guards[*index];
+            if !seen[*index].insert(state(map.coord(guard.position), guard)) {
+                looped_at[*index].get_or_insert(steps);
+            }
+        }
+        if active.iter().all(|index| looped_at[*index].is_some()) {
+            let snapshot = active.iter().map(|index| guards[*index]).collect();
+            if !together.insert(snapshot) {
+                for index in active {
+                    fates[index] = looped_at[index].map(|steps| Fate::Looped { steps });
+                }
+                return Lockstep {
+                    fates: fates.into_iter().flatten().collect(),
+                    looped: true,
+                    steps,
+                };
+            }
+        }
+
+        let before: Vec<Guard> = guards.clone();
+        steps += 1;
+        let mut walking = vec![];
+        for index in active {
+            match map.move_guard(&mut guards[index]) {
+                StepOutcome::Exited => fates[index] = Some(Fate::Exited { steps }),
+                _ => walking.push(index),
+            }
+        }
+
+        let stopped: Vec<usize> = (0..guards.len())
+            .filter(|index| matches!(fates[*index], Some(Fate::Collided { .. })))
+            .collect();
+        let mut hit: Vec<Vec<usize>> = vec![vec![]; guards.len()];
+        for (i, a) in walking.iter().enumerate() {
+            for b in walking.iter().skip(i + 1) {
+                let same = guards[*a].position == guards[*b].position;
+                // meeting halfway, either swapping cells or crossing over diagonally
+                let halfway = |index: usize| {
+                    let (from, to) = (before[index].position, guards[index].position);
+                    (from.0 + to.0, from.1 + to.1)
+                };
+                let crossed =
+                    before[*a].position != before[*b].position && halfway(*a) == halfway(*b);
+                if same || crossed {
+                    hit[*a].push(*b);
+                    hit[*b].push(*a);
+                }
+            }
+            for b in stopped.iter() {
+                if guards[*a].position == guards[*b].position {
+                    hit[*a].push(*b);
+                }
+            }
+        }
+        for index in walking {
+            if !hit[index].is_empty() {
+                hit[index].sort();
+                fates[index] = Some(Fate::Collided {
+                    with: hit[index].clone(),
+                    steps,
+                });
+            }
+        }
+    }
+
+    Lockstep {
+        fates: fates.into_iter().flatten().collect(),
+        looped: false,
+        steps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        guards::{lockstep, Fate, Rules, Turn},
+        Direction, Map, StepOutcome,
+    };
+
+    #[test]
+    fn should_turn_by_rules() {
+        let input = ".#.
+                     .^.
+                     ...";
+
+        let mut map = Map::parse(input);
+        map.rules.turn = Turn::Left;
+        assert_eq!(map.step(), StepOutcome::Turned);
+        assert_eq!(map.guard.facing, Direction::West);
+
+        let mut map = Map::parse(input);
+        map.rules.turn = Turn::Reverse;
+        map.step();
+        assert_eq!(map.guard.facing, Direction::South);
+        assert_eq!(map.step(), StepOutcome::Moved);
+        assert_eq!(map.guard.position, (1, 2));
+    }
+
+    #[test]
+    fn should_move_diagonally() {
+        let mut map = Map::parse(
+            "..#
+             ...
+             >..",
+        );
+        map.rules = Rules {
+            turn: Turn::Right,
+            diagonal: true,
+        };
+
+        // `>` goes south east, which is straight off the map
+        assert_eq!(map.step(), StepOutcome::Exited);
+
+        map.guard.facing = Direction::North;
+        assert_eq!(map.step(), StepOutcome::Moved);
+        assert_eq!(map.guard.position, (1, 1));
+        // blocked north east at (2, 0), so turns to head south east
+        assert_eq!(map.step(), StepOutcome::Turned);
+        assert_eq!(map.guard.facing, Direction::East);
+        assert_eq!(map.step(), StepOutcome::Moved);
+        assert_eq!(map.guard.position, (2, 2));
+    }
+
+    #[test]
+    fn should_keep_every_guard() {
+        let map = Map::parse(
+            "^..
+             ..<
+             .v.",
+        );
+
+        assert_eq!(map.guards.len(), 3);
+        assert_eq!(map.start, map.guards[0]);
+        assert_eq!(map.guards[1].position, (2, 1));
+        assert_eq!(map.guards[2].facing, Direction::South);
+    }
+
+    #[test]
+    fn should_collide() {
+        // meet in the middle
+        let report = lockstep(&Map::parse(">.<"));
+        assert_eq!(
+            report.fates,
+            vec![
+                Fate::Collided {
+                    with: vec![1],
+                    steps: 1
+                },
+                Fate::Collided {
+                    with: vec![0],
+                    steps: 1
+                },
+            ]
+        );
+        assert!(!report.looped);
+
+        // pass through each other between cells
+        let map = Map::parse("><");
+        let both = vec![
+            Fate::Collided {
+                with: vec![1],
+                steps: 1,
+            },
+            Fate::Collided {
+                with: vec![0],
+                steps: 1,
+            },
+        ];
+        assert_eq!(lockstep(&map).fates, both);
+
+        // cross over diagonally, south east and south west
+        let mut map = Map::parse(
+            ">v
+             ..",
+        );
+        map.rules.diagonal = true;
+        assert_eq!(lockstep(&map).fates, both);
+    }
+
+    #[test]
+    fn should_collide_with_stopped_guards() {
+        // the first two stop at (1, 0), then the third walks into them
+        let report = lockstep(&Map::parse(
+            ">.<
+             ...
+             .^.",
+        ));
+        assert_eq!(
+            report.fates[2],
+            Fate::Collided {
+                with: vec![0, 1],
+                steps: 2
+            }
+        );
+        assert_eq!(
+            report.to_string().lines().nth(2),
+            Some("guard 2 ran into guard 0 and 1 after 2 steps")
+        );
+
+        // three at once
+        let report = lockstep(&Map::parse(
+            ".v.
+             >.<",
+        ));
+        assert_eq!(
+            report.fates,
+            vec![
+                Fate::Collided {
+                    with: vec![1, 2],
+                    steps: 1
+                },
+                Fate::Collided {
+                    with: vec![0, 2],
+                    steps: 1
+                },
+                Fate::Collided {
+                    with: vec![0, 1],
+                    steps: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_find_loops_per_guard_and_overall() {
+        let input = ".#...
+                     ....#
+                     #^...
+                     ...#.
+                     ....v";
+        let report = lockstep(&Map::parse(input));
+
+        assert_eq!(
+            report.fates,
+            vec![Fate::Looped { steps: 10 }, Fate::Exited { steps: 1 }]
+        );
+        assert!(report.looped);
+        assert_eq!(
+            report.to_string(),
+            "guard 0 is in a loop after 10 steps
+guard 1 left after 1 steps
+patrol loops forever after 20 steps"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{state, Direction, Guard, Map, Position};
+
+// one bit per cell, or per cell and direction
+#[derive(Debug, Clone)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(size: usize) -> Self {
+        BitSet {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    // false if it was already there
+    pub fn insert(&mut self, index: usize) -> bool {
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        added
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Walk {
+    // in the order the guard first gets to them
+    pub cells: Vec<Position>,
+    // every obstacle the guard turns at, in order
+    pub hit: Vec<Position>,
+    pub looped: bool,
+}
+
+// for every cell, where the nearest obstacle is in each direction, so the guard can walk
+// straight to the next turn instead of one cell at a time
+#[derive(Debug)]
+pub struct Jumps {
+    width: usize,
+    height: usize,
+    // x of the nearest obstacle east/west, y north/south, indexed by `Direction as usize`
+    next: Vec<[Option<usize>; 4]>,
+}
+
+impl Jumps {
+    pub fn new(map: &Map) -> Self {
+        let (width, height, blocked) = (map.width, map.height, &map.blocked);
+
+        let mut next = vec![[None; 4]; width * height];
+        for y in 0..height {
+            let mut nearest = None;
+            for x in 0..width {
+                next[(y * width) + x][Direction::West as usize] = nearest;
+                if blocked[(y * width) + x] {
+                    nearest = Some(x);
+                }
+            }
+            let mut nearest = None;
+            for x in (0..width).rev() {
+                next[(y * width) + x][Direction::East as usize] = nearest;
+                if blocked[(y * width) + x] {
+                    nearest = Some(x);
+                }
+            }
+        }
+        for x in 0..width {
+            let mut nearest = None;
+            for y in 0..height {
+                next[(y * width) + x][Direction::North as usize] = nearest;
+                if blocked[(y * width) + x] {
+                    nearest = Some(y);
+                }
+            }
+            let mut nearest = None;
+            for y in (0..height).rev() {
+                next[(y * width) + x][Direction::South as usize] = nearest;
+                if blocked[(y * width) + x] {
+                    nearest = Some(y);
+                }
+            }
+        }
+
+        Jumps {
+            width,
+            height,
+            next,
+        }
+    }
+
+    fn coord(&self, position: Position) -> usize {
+        (position.1 * self.width) + position.0
+    }
+
+    // where the guard stops walking straight ahead: just before an obstacle, or None if
+    // it walks off the map. `extra` is one more obstacle on top of the map's
+    fn ahead(&self, guard: &Guard, extra: Option<Position>) -> Option<Position> {
+        let (x, y) = guard.position;
+        let next = self.next[self.coord(guard.position)][guard.facing as usize];
+
+        match guard.facing {
+            Direction::North => {
+                let extra = extra.filter(|e| e.0 == x && e.1 < y).map(|e| e.1);
+                next.into_iter().chain(extra).max().map(|y| (x, y + 1))
+            }
+            Direction::East => {
+                let extra = extra.filter(|e| e.1 == y && e.0 > x).map(|e| e.0);
+                next.into_iter().chain(extra).min().map(|x| (x - 1, y))
+            }
+            Direction::South => {
+                let extra = extra.filter(|e| e.0 == x && e.1 > y).map(|e| e.1);
+                next.into_iter().chain(extra).min().map(|y| (x, y - 1))
+            }
+            Direction::West => {
+                let extra = extra.filter(|e| e.1 == y && e.0 < x).map(|e| e.0);
+                next.into_iter().chain(extra).max().map(|x| (x + 1, y))
+            }
+        }
+    }
+
+    // the last cell before the guard walks off the map
+    fn edge(&self, guard: &Guard) -> Position {
+        let (x, y) = guard.position;
+        match guard.facing {
+            Direction::North => (x, 0),
+            Direction::East => (self.width - 1, y),
+            Direction::South => (x, self.height - 1),
+            Direction::West => (0, y),
+        }
+    }
+
+    // the whole patrol, until the guard leaves or makes a turn it's already made
+    pub fn walk(&self, start: Guard) -> Walk {
+        let mut seen = BitSet::new(self.width * self.height);
+        let mut turns = BitSet::new(self.width * self.height * 4);
+        let mut walk = Walk {
+            cells: vec![start.position],
+            hit: vec![],
+            looped: false,
+        };
+        seen.insert(self.coord(start.position));
+        let mut guard = start;
+
+        loop {
+            let stop = self.ahead(&guard, None);
+            let end = stop.unwrap_or_else(|| self.edge(&guard));
+            let (dx, dy) = guard.facing.delta();
+            while guard.position != end {
+                guard.position.0 = guard.position.0.wrapping_add_signed(dx);
+                guard.position.1 = guard.position.1.wrapping_add_signed(dy);
+                if seen.insert(self.coord(guard.position)) {
+                    walk.cells.push(guard.position);
+                }
+            }
+
+            if stop.is_none() {
+                return walk;
+            }
+            walk.hit
+                .push((end.0.wrapping_add_signed(dx), end.1.wrapping_add_signed(dy)));
+            if !turns.insert(state(self.coord(guard.position), &guard)) {
+                walk.looped = true;
+                return walk;
+            }
+            guard.turn();
+        }
+    }
+
+    // whether the guard goes round forever with one more obstacle at `extra`. Only turns
+    // are recorded, in `turns` (big enough for every position and facing), which is cleared
+    // first so one set can be reused for every check
+    pub fn loops(&self, start: Guard, extra: Option<Position>, turns: &mut BitSet) -> bool {
+        turns.clear();
+        let mut guard = start;
+
+        while let Some(position) = self.ahead(&guard, extra) {
+            guard.position = position;
+            if !turns.insert(state(self.coord(guard.position), &guard)) {
+                return true;
+            }
+            guard.turn();
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        jump::{BitSet, Jumps},
+        Direction, Guard, Map,
+    };
+
+    const INPUT: &str = "....#.....
+        .........#
+        ..........
+        ..#.......
+        .......#..
+        ..........
+        .#..^.....
+        ........#.
+        #.........
+        ......#...";
+
+    #[test]
+    fn should_jump_to_next_turn() {
+        let map = Map::parse(INPUT);
+        let jumps = Jumps::new(&map);
+
+        assert_eq!(jumps.ahead(&map.start, None), Some((4, 1)));
+        let east = Guard {
+            position: (4, 1),
+            facing: Direction::East,
+        };
+        assert_eq!(jumps.ahead(&east, None), Some((8, 1)));
+        assert_eq!(jumps.ahead(&east, Some((6, 1))), Some((5, 1)));
+        // behind the guard, so doesn't count
+        assert_eq!(jumps.ahead(&east, Some((2, 1))), Some((8, 1)));
+
+        let west = Guard {
+            position: (5, 5),
+            facing: Direction::West,
+        };
+        assert_eq!(jumps.ahead(&west, None), None);
+    }
+
+    #[test]
+    fn should_walk_whole_patrol() {
+        let map = Map::parse(INPUT);
+        let walk = Jumps::new(&map).walk(map.start);
+
+        assert_eq!(walk.cells.len(), 41);
+        assert_eq!(walk.hit.len(), 10);
+        assert_eq!(walk.hit[..3], [(4, 0), (9, 1), (8, 7)]);
+        assert!(!walk.looped);
+
+        let mut map = Map::parse(INPUT);
+        map.add_obstacle((3, 6));
+        let walk = Jumps::new(&map).walk(map.start);
+        assert!(walk.looped);
+        assert_eq!(walk.hit, vec![(4, 0), (9, 1), (8, 7), (3, 6), (4, 0)]);
+    }
+
+    #[test]
+    fn should_find_loops() {
+        let map = Map::parse(INPUT);
+        let jumps = Jumps::new(&map);
+        let mut turns = BitSet::new(map.width * map.height * 4);
+
+        assert!(!jumps.loops(map.start, None, &mut turns));
+        assert!(jumps.loops(map.start, Some((3, 6)), &mut turns));
+        assert!(jumps.loops(map.start, Some((7, 9)), &mut turns));
+        assert!(!jumps.loops(map.start, Some((5, 5)), &mut turns));
+    }
+}
@@ -1,20 +1,150 @@
-use std::{collections::HashSet, env::args, fs::read_to_string, io};
+use std::{
+    collections::HashSet,
+    env::args,
+    fmt::Display,
+    fs::{create_dir_all, read_to_string, write},
+    io,
+    path::Path,
+    thread::{self, available_parallelism},
+    time::Duration,
+};
 
+use generate::{generate, Settings};
+use guards::{lockstep, Rules, Turn};
+use jump::{BitSet, Jumps};
+use obstruct::{free, trap};
+use render::{animate, dump, Replay};
+
+mod generate;
+mod guards;
+mod jump;
+mod obstruct;
+mod render;
 mod test;
 
 fn main() -> Result<(), io::Error> {
     let args: Vec<String> = args().collect();
 
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .map(|index| args.get(index + 1).expect("Missing value for option"))
+    };
+
+    // write out a random map instead, eg
+    // --generate big.txt --size 130x130 --density 0.015 --seed 1 --guard 65,70 --facing ^ --ensure loop
+    if let Some(out) = option("--generate") {
+        let defaults = Settings::default();
+        let pair = |name: &str, separator: char| {
+            option(name).map(|value| {
+                let (a, b) = value
+                    .split_once(separator)
+                    .unwrap_or_else(|| panic!("{} should look like 1{}2", name, separator));
+                (
+                    a.parse::<usize>().expect("Should be a number"),
+                    b.parse::<usize>().expect("Should be a number"),
+                )
+            })
+        };
+        let (width, height) = pair("--size", 'x').unwrap_or((defaults.width, defaults.height));
+        let facing = match option("--facing").map(|facing| facing.as_str()) {
+            None | Some("^") => Direction::North,
+            Some(">") => Direction::East,
+            Some("v") => Direction::South,
+            Some("<") => Direction::West,
+            Some(other) => panic!("Unknown facing {}, expected one of ^>v<", other),
+        };
+        let settings = Settings {
+            width,
+            height,
+            density: option("--density").map_or(defaults.density, |density| {
+                density.parse().expect("--density should be a number")
+            }),
+            guard: pair("--guard", ','),
+            facing,
+            ensure: option("--ensure")
+                .map(|ensure| ensure.parse())
+                .transpose()?,
+            seed: option("--seed").map_or(defaults.seed, |seed| {
+                seed.parse().expect("--seed should be a number")
+            }),
+        };
+
+        return match generate(&settings)? {
+            Some(text) => write(out, text + "\n"),
+            None => Err(io::Error::other(
+                "couldn't make a map with that patrol, try another seed or density",
+            )),
+        };
+    }
+
     let filename = &args[1];
     let input = read_to_string(filename)?;
 
+    // replay the patrols in the terminal, or write out every frame of them
+    let animate_patrols = args.iter().any(|arg| arg == "--animate");
+    let delay = Duration::from_millis(
+        option("--delay")
+            .map(|ms| ms.parse().expect("--delay should be in milliseconds"))
+            .unwrap_or(100),
+    );
+    let frames = option("--frames").map(Path::new);
+
     // let mut game_over = false;
     // calc straight line to next obstacle or bounds
     let mut map = Map::parse(&input);
+    map.rules = Rules {
+        turn: option("--turn").map_or(Ok(Turn::Right), |turn| turn.parse())?,
+        diagonal: args.iter().any(|arg| arg == "--diagonal"),
+    };
+
+    // the puzzle only has one guard
+    if map.guards.len() > 1 {
+        println!("{}", lockstep(&map));
+        return Ok(());
+    }
+
+    // fewest obstacles to add to trap the guard, or take away to let it out, up to a limit.
+    // only for the puzzle's rules
+    let limit = |name: &str| {
+        option(name).map(|limit| {
+            limit
+                .parse::<usize>()
+                .unwrap_or_else(|_| panic!("{} needs a number of obstacles", name))
+        })
+    };
+    if limit("--trap").is_some() || limit("--free").is_some() {
+        if map.rules != Rules::default() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--trap and --free only work with the puzzle's rules",
+            ));
+        }
+        if let Some(limit) = limit("--trap") {
+            match trap(&map, limit) {
+                Some(obstacles) => println!("Trapped by adding {:?}", obstacles),
+                None => println!("Can't trap the guard with {} obstacles or fewer", limit),
+            }
+        }
+        if let Some(limit) = limit("--free") {
+            match free(&map, limit) {
+                Some(obstacles) => println!("Let out by removing {:?}", obstacles),
+                None => println!("Can't let the guard out with {} obstacles or fewer", limit),
+            }
+        }
+    }
+
     let part_one = run(&mut map);
     match part_one {
         Some(positions) => {
             println!("Part one: {}", positions.len());
+            if animate_patrols {
+                animate(Replay::new(&map, None), delay);
+            }
+            if let Some(dir) = frames {
+                create_dir_all(dir)?;
+                write(dir.join("part-one.txt"), dump(Replay::new(&map, None)))?;
+            }
 
             // part 2
             // create guard route
@@ -22,130 +152,273 @@ fn main() -> Result<(), io::Error> {
             // add obstacle and run entire game
             // check if stuck in a loop
             // loop = adding same guard pos and direction
-            // note takes a while
-            let loop_total = check_for_loops(positions, &mut map);
-            // off by one :shrug:
-            println!("part two: {:?}", loop_total);
+            let loops = check_for_loops(&positions, &map);
+            println!("part two: {:?}", loops.len());
+
+            let mut loops: Vec<Position> = loops.into_iter().collect();
+            loops.sort();
+            for obstruction in loops.iter().copied() {
+                if animate_patrols {
+                    animate(Replay::new(&map, Some(obstruction)), delay);
+                }
+                if let Some(dir) = frames {
+                    let name = format!("loop-{}-{}.txt", obstruction.0, obstruction.1);
+                    write(dir.join(name), dump(Replay::new(&map, Some(obstruction))))?;
+                }
+            }
+
+            // which obstacles make the tightest loops
+            if args.iter().any(|arg| arg == "--analyse") {
+                let mut outcomes: Vec<(Position, PatrolOutcome)> = loops
+                    .iter()
+                    .map(|obstruction| {
+                        let mut map = map.clone();
+                        map.guard = map.start;
+                        map.add_obstacle(*obstruction);
+                        (*obstruction, patrol(&mut map).1)
+                    })
+                    .collect();
+                outcomes.sort_by_key(|(_, outcome)| outcome.cycle());
+                for (obstruction, outcome) in outcomes {
+                    println!("obstacle at {:?}: {}", obstruction, outcome);
+                }
+            }
         }
 
-        None => println!("uh oh"),
+        None => {
+            map.guard = map.start;
+            println!("uh oh, the guard {}", patrol(&mut map).1);
+        }
     }
 
     Ok(())
 }
 
+// the puzzle's rules jump from turn to turn, the same as the loop check
 fn run(map: &mut Map) -> Option<HashSet<Position>> {
-    let mut route = HashSet::new();
+    if map.rules == Rules::default() {
+        let walk = Jumps::new(map).walk(map.guard);
+        return (!walk.looped).then(|| walk.cells.into_iter().collect());
+    }
+
+    match patrol(map) {
+        // get unique positions by stripping direction
+        (route, PatrolOutcome::Exited { .. }) => {
+            Some(route.iter().map(|guard| guard.position).collect())
+        }
+        (_, PatrolOutcome::Looped { .. }) => None,
+    }
+}
 
-    'game: loop {
-        let worked = route.insert(map.guard);
+// walks the guard until it leaves or loops, along with every position and facing it had on
+// the way
+fn patrol(map: &mut Map) -> (Vec<Guard>, PatrolOutcome) {
+    // which step each position and facing was first reached at
+    let mut seen: Vec<Option<usize>> = vec![None; map.width * map.height * 4];
+    let mut route: Vec<Guard> = vec![];
+    let mut turned: Vec<usize> = vec![];
 
-        if !worked {
+    loop {
+        let state = state(map.coord(map.guard.position), &map.guard);
+        if let Some(tail) = seen[state] {
             // it's a loop!
-            return None;
+            let turns = turned
+                .iter()
+                .filter(|step| **step >= tail)
+                .map(|step| route[*step].position)
+                .collect();
+            let cycle = route.len() - tail;
+            return (route, PatrolOutcome::Looped { tail, cycle, turns });
         }
-        map.step();
-        if map.out_of_bounds(&map.guard.position) {
-            break 'game;
+        seen[state] = Some(route.len());
+        route.push(map.guard);
+
+        match map.step() {
+            StepOutcome::Moved => {}
+            StepOutcome::Turned => turned.push(route.len() - 1),
+            StepOutcome::Exited => {
+                let outcome = PatrolOutcome::Exited {
+                    position: map.guard.position,
+                    steps: route.len(),
+                };
+                return (route, outcome);
+            }
         }
     }
+}
 
-    // let mut debug = map.map.clone();
-
-    // map.guard_route.clone().iter().for_each(|v| {
-    // debug[map.coord(v.position.0, v.position.1)] = 'X';
-    // });
+// jumps from turn to turn rather than stepping, if the guard moves like in the puzzle.
+// Every candidate is the same map plus one obstacle, so they're shared out between threads,
+// each reusing its own set of turns.
+// the guard would see an obstacle put where it's standing, so the start doesn't count
+fn check_for_loops(route: &HashSet<Position>, map: &Map) -> HashSet<Position> {
+    let jumps = (map.rules == Rules::default()).then(|| Jumps::new(map));
+    let loops = |obstruction: Position, turns: &mut BitSet| match &jumps {
+        Some(jumps) => jumps.loops(map.start, Some(obstruction), turns),
+        None => {
+            let mut map = map.clone();
+            map.guard = map.start;
+            map.add_obstacle(obstruction);
+            run(&mut map).is_none()
+        }
+    };
+    let candidates: Vec<Position> = route
+        .iter()
+        .filter(|pos| **pos != map.start.position)
+        .copied()
+        .collect();
+    let threads = available_parallelism().map_or(1, |n| n.get());
+    let size = candidates.len().div_ceil(threads).max(1);
 
-    // dbg!(debug);
+    thread::scope(|scope| {
+        let handles: Vec<_> = candidates
+            .chunks(size)
+            .map(|chunk| {
+                let loops = &loops;
+                scope.spawn(move || {
+                    let mut turns = BitSet::new(map.width * map.height * 4);
+                    chunk
+                        .iter()
+                        .filter(|pos| loops(**pos, &mut turns))
+                        .copied()
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
 
-    // get unique positions by stripping direction
-    let positions: HashSet<_> = route.iter().map(|guard| guard.position).collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Loop check panicked"))
+            .collect()
+    })
+}
 
-    Some(positions)
+// position and facing together, for indexing a `BitSet`
+fn state(coord: usize, guard: &Guard) -> usize {
+    (coord * 4) + guard.facing as usize
 }
 
-fn check_for_loops(route: HashSet<Position>, map: &mut Map) -> i32 {
-    let mut loops = 0;
+type Position = (usize, usize);
 
-    for pos in route.iter() {
-        // reset
-        map.guard.position = map.start.position;
-        map.guard.facing = map.start.facing;
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum StepOutcome {
+    Moved,
+    Turned,
+    Exited,
+}
 
-        map.obstacles.push(*pos);
+#[derive(PartialEq, Debug)]
+enum PatrolOutcome {
+    // the last cell before walking off, and how many steps that took counting the last one
+    Exited {
+        position: Position,
+        steps: usize,
+    },
+    // steps before first getting into the loop, steps round it, and where it turns on the way
+    Looped {
+        tail: usize,
+        cycle: usize,
+        turns: Vec<Position>,
+    },
+}
 
-        if run(map).is_none() {
-            loops += 1;
+impl PatrolOutcome {
+    fn cycle(&self) -> Option<usize> {
+        match self {
+            PatrolOutcome::Exited { .. } => None,
+            PatrolOutcome::Looped { cycle, .. } => Some(*cycle),
         }
-        map.obstacles.remove(map.obstacles.len() - 1);
     }
-
-    loops
 }
 
-type Position = (usize, usize);
+impl Display for PatrolOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatrolOutcome::Exited { position, steps } => {
+                write!(f, "leaves from {:?} after {} steps", position, steps)
+            }
+            PatrolOutcome::Looped { tail, cycle, turns } => write!(
+                f,
+                "loops after {} steps, {} steps round turning at {}",
+                tail,
+                cycle,
+                turns
+                    .iter()
+                    .map(|turn| format!("{:?}", turn))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
 
 #[derive(PartialEq, Debug, Clone)]
 struct Map {
     map: Vec<char>,
     width: usize,
     height: usize,
-    obstacles: Vec<Position>, // or hashmap
+    obstacles: Vec<Position>,
+    // whether each cell has an obstacle, indexed by `coord`
+    blocked: Vec<bool>,
+    // every guard, in reading order
+    guards: Vec<Guard>,
+    rules: Rules,
     start: Guard,
     guard: Guard,
 }
 
 impl Map {
-    // fn coord(&self, x: usize, y: usize) -> usize {
-    //     (y * self.width) + x
-    // }
+    fn coord(&self, pos: Position) -> usize {
+        (pos.1 * self.width) + pos.0
+    }
 
-    fn out_of_bounds(&self, pos: &Position) -> bool {
-        let x_out_of_bounds = pos.0 == 0 || pos.0 >= self.width;
-        let y_out_of_bounds = pos.1 == 0 || pos.1 >= self.height;
-        x_out_of_bounds || y_out_of_bounds
+    fn add_obstacle(&mut self, pos: Position) {
+        let coord = self.coord(pos);
+        self.blocked[coord] = true;
+        self.obstacles.push(pos);
     }
 
-    fn step(&mut self) {
-        match self.guard.facing {
-            Direction::East => {
-                self.guard.position.0 += 1;
-                if self.obstacles.contains(&self.guard.position) {
-                    self.guard.turn();
-                    self.guard.position.0 -= 1;
-                }
-            }
-            Direction::South => {
-                self.guard.position.1 += 1;
-                if self.obstacles.contains(&self.guard.position) {
-                    self.guard.turn();
-                    self.guard.position.1 -= 1;
-                }
-            }
-            Direction::West => {
-                self.guard.position.0 -= 1;
-                if self.obstacles.contains(&self.guard.position) {
-                    self.guard.turn();
-                    self.guard.position.0 += 1;
-                }
+    fn remove_obstacle(&mut self, pos: Position) {
+        let coord = self.coord(pos);
+        self.blocked[coord] = false;
+        self.obstacles.retain(|obstacle| *obstacle != pos);
+    }
+
+    // the next cell in front of the guard, or None if that's off the map
+    fn ahead(&self, guard: &Guard) -> Option<Position> {
+        let (dx, dy) = self.rules.delta(guard.facing);
+        let x = guard.position.0.checked_add_signed(dx)?;
+        let y = guard.position.1.checked_add_signed(dy)?;
+
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    fn step(&mut self) -> StepOutcome {
+        let mut guard = self.guard;
+        let outcome = self.move_guard(&mut guard);
+        self.guard = guard;
+        outcome
+    }
+
+    // the guard stays on its last cell when it walks off the map
+    fn move_guard(&self, guard: &mut Guard) -> StepOutcome {
+        match self.ahead(guard) {
+            Some(next) if self.blocked[self.coord(next)] => {
+                guard.facing = self.rules.turn(guard.facing);
+                StepOutcome::Turned
             }
-            Direction::North => {
-                self.guard.position.1 -= 1;
-                if self.obstacles.contains(&self.guard.position) {
-                    self.guard.turn();
-                    self.guard.position.1 += 1;
-                }
+            Some(next) => {
+                guard.position = next;
+                StepOutcome::Moved
             }
+            None => StepOutcome::Exited,
         }
     }
 
     fn parse(input: &str) -> Self {
         let mut height = 0;
         let mut obstacles = Vec::new();
-        let mut guard = Guard {
-            facing: Direction::North,
-            position: (0, 0),
-        };
+        let mut guards = Vec::new();
 
         let map: Vec<char> = input
             .lines()
@@ -157,28 +430,28 @@ impl Map {
                         obstacles.push((index, height));
                     }
                     if *p == '^' {
-                        guard = Guard {
+                        guards.push(Guard {
                             facing: Direction::North,
                             position: (index, height),
-                        };
+                        });
                     }
                     if *p == '>' {
-                        guard = Guard {
+                        guards.push(Guard {
                             facing: Direction::East,
                             position: (index, height),
-                        };
+                        });
                     }
                     if *p == 'v' {
-                        guard = Guard {
+                        guards.push(Guard {
                             facing: Direction::South,
                             position: (index, height),
-                        };
+                        });
                     }
                     if *p == '<' {
-                        guard = Guard {
+                        guards.push(Guard {
                             facing: Direction::West,
                             position: (index, height),
-                        };
+                        });
                     }
                 });
                 height += 1;
@@ -186,12 +459,24 @@ impl Map {
             })
             .collect();
         let width = map.len() / height;
+        let guard = guards.first().copied().unwrap_or(Guard {
+            facing: Direction::North,
+            position: (0, 0),
+        });
+
+        let mut blocked = vec![false; width * height];
+        for (x, y) in obstacles.iter() {
+            blocked[(y * width) + x] = true;
+        }
 
         Map {
             width,
             height,
             obstacles,
-            start: guard.clone(),
+            blocked,
+            guards,
+            rules: Rules::default(),
+            start: guard,
             map,
             guard,
         }
@@ -230,3 +515,23 @@ enum Direction {
     South,
     West,
 }
+
+impl Direction {
+    fn clockwise(&self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+}
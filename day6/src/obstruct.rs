@@ -0,0 +1,138 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{jump::Jumps, Map, Position};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Change {
+    Add,
+    Remove,
+}
+
+// depth first with exactly `left` more changes. `tried` has every set of changes already
+// searched, since the same set can come up in a different order
+fn search(
+    map: &Map,
+    change: Change,
+    left: usize,
+    chosen: &mut Vec<Position>,
+    tried: &mut HashSet<Vec<Position>>,
+) -> Option<Vec<Position>> {
+    let walk = Jumps::new(map).walk(map.start);
+    let done = match change {
+        Change::Add => walk.looped,
+        Change::Remove => !walk.looped,
+    };
+    if done {
+        let mut found = chosen.clone();
+        found.sort();
+        return Some(found);
+    }
+    if left == 0 {
+        return None;
+    }
+
+    // nothing off the guard's route makes a difference to where it goes
+    let candidates: Vec<Position> = match change {
+        // the guard would see an obstacle put where it's standing
+        Change::Add => walk
+            .cells
+            .into_iter()
+            .filter(|cell| *cell != map.start.position)
+            .collect(),
+        Change::Remove => walk
+            .hit
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    };
+
+    for candidate in candidates {
+        chosen.push(candidate);
+        let mut key = chosen.clone();
+        key.sort();
+
+        if tried.insert(key) {
+            let mut next = map.clone();
+            match change {
+                Change::Add => next.add_obstacle(candidate),
+                Change::Remove => next.remove_obstacle(candidate),
+            }
+            if let Some(found) = search(&next, change, left - 1, chosen, tried) {
+                return Some(found);
+            }
+        }
+        chosen.pop();
+    }
+
+    None
+}
+
+// fewest obstacles to put down so the guard never leaves, trying up to `limit` of them
+pub fn trap(map: &Map, limit: usize) -> Option<Vec<Position>> {
+    (0..=limit).find_map(|size| search(map, Change::Add, size, &mut vec![], &mut HashSet::new()))
+}
+
+// fewest obstacles to take away so a guard going round in a loop gets out, trying up to
+// `limit` of them
+pub fn free(map: &Map, limit: usize) -> Option<Vec<Position>> {
+    (0..=limit).find_map(|size| search(map, Change::Remove, size, &mut vec![], &mut HashSet::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        obstruct::{free, trap},
+        run, Map,
+    };
+
+    const INPUT: &str = "....#.....
+        .........#
+        ..........
+        ..#.......
+        .......#..
+        ..........
+        .#..^.....
+        ........#.
+        #.........
+        ......#...";
+
+    #[test]
+    fn should_trap_with_one_obstacle() {
+        let map = Map::parse(INPUT);
+
+        // the first one the guard gets to, which is part of part two's answer
+        assert_eq!(trap(&map, 1), Some(vec![(3, 6)]));
+        assert_eq!(trap(&map, 0), None);
+    }
+
+    #[test]
+    fn should_trap_empty_map() {
+        let map = Map::parse(
+            "....
+             ....
+             .^..
+             ....",
+        );
+
+        assert_eq!(trap(&map, 3), None);
+        let obstacles = trap(&map, 4).unwrap();
+        assert_eq!(obstacles.len(), 4);
+
+        let mut trapped = map.clone();
+        for obstacle in obstacles {
+            trapped.add_obstacle(obstacle);
+        }
+        assert_eq!(run(&mut trapped), None);
+    }
+
+    #[test]
+    fn should_free_looping_guard() {
+        let mut map = Map::parse(INPUT);
+        assert_eq!(free(&map, 1), Some(vec![]));
+
+        map.add_obstacle((3, 6));
+        assert_eq!(free(&map, 0), None);
+        assert_eq!(free(&map, 1), Some(vec![(3, 6)]));
+    }
+}
@@ -0,0 +1,143 @@
+use std::{thread::sleep, time::Duration};
+
+use crate::{jump::BitSet, state, Direction, Map, Position, StepOutcome};
+
+pub const EMPTY: char = '.';
+pub const OBSTACLE: char = '#';
+pub const VISITED: char = 'X';
+// the obstacle being tried in part two
+pub const OBSTRUCTION: char = 'O';
+
+pub fn glyph(facing: Direction) -> char {
+    match facing {
+        Direction::North => '^',
+        Direction::East => '>',
+        Direction::South => 'v',
+        Direction::West => '<',
+    }
+}
+
+// the patrol from the start one step at a time, with `extra` as one more obstacle. Ends
+// once the guard walks off, or gets back somewhere it's already been facing the same way
+pub struct Replay {
+    map: Map,
+    extra: Option<Position>,
+    visited: Vec<bool>,
+    seen: BitSet,
+    done: bool,
+}
+
+impl Replay {
+    pub fn new(map: &Map, extra: Option<Position>) -> Self {
+        let mut map = map.clone();
+        map.guard = map.start;
+        if let Some(extra) = extra {
+            map.add_obstacle(extra);
+        }
+
+        Replay {
+            extra,
+            visited: vec![false; map.width * map.height],
+            seen: BitSet::new(map.width * map.height * 4),
+            done: false,
+            map,
+        }
+    }
+
+    fn draw(&self) -> String {
+        (0..self.map.height)
+            .map(|y| {
+                (0..self.map.width)
+                    .map(|x| {
+                        let coord = self.map.coord((x, y));
+                        if (x, y) == self.map.guard.position {
+                            glyph(self.map.guard.facing)
+                        } else if Some((x, y)) == self.extra {
+                            OBSTRUCTION
+                        } else if self.map.blocked[coord] {
+                            OBSTACLE
+                        } else if self.visited[coord] {
+                            VISITED
+                        } else {
+                            EMPTY
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Iterator for Replay {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let guard = self.map.guard;
+        let coord = self.map.coord(guard.position);
+        self.visited[coord] = true;
+        let frame = self.draw();
+
+        self.done =
+            !self.seen.insert(state(coord, &guard)) || self.map.step() == StepOutcome::Exited;
+        Some(frame)
+    }
+}
+
+// redraws over the top of the previous frame
+pub fn animate(replay: Replay, delay: Duration) {
+    for frame in replay {
+        println!("\x1b[2J\x1b[H{}", frame);
+        sleep(delay);
+    }
+}
+
+// every frame, with a blank line between each
+pub fn dump(replay: Replay) -> String {
+    replay.collect::<Vec<_>>().join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{render::Replay, Map};
+
+    #[test]
+    fn should_draw_each_step() {
+        let map = Map::parse(
+            "#..
+             ...
+             ^..",
+        );
+        let frames: Vec<String> = Replay::new(&map, None).collect();
+
+        assert_eq!(
+            frames,
+            vec![
+                "#..\n...\n^..",
+                "#..\n^..\nX..",
+                "#..\n>..\nX..",
+                "#..\nX>.\nX..",
+                "#..\nXX>\nX..",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_show_obstruction_and_stop_at_loop() {
+        let map = Map::parse(
+            ".#..
+             ...#
+             .^..
+             ..#.",
+        );
+        let frames: Vec<String> = Replay::new(&map, Some((0, 2))).collect();
+
+        assert_eq!(frames.first().unwrap(), ".#..\n...#\nO^..\n..#.");
+        // back at the start facing north again
+        assert_eq!(frames.last().unwrap(), ".#..\n.XX#\nO^X.\n..#.");
+    }
+}
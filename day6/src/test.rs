@@ -2,7 +2,11 @@
 mod test {
     use std::collections::HashSet;
 
-    use crate::{run, Direction, Guard, Map};
+    use crate::{
+        check_for_loops,
+        guards::{Rules, Turn},
+        run, Direction, Guard, Map, Position,
+    };
 
     #[test]
     fn it_should_create_map() {
@@ -24,34 +28,38 @@ mod test {
         };
         guard_route.insert(guard);
 
-        assert_eq!(
-            map,
-            Map {
-                width: 10,
-                height: 10,
-                obstacles: vec![
-                    (4, 0),
-                    (9, 1),
-                    (2, 3),
-                    (7, 4),
-                    (1, 6),
-                    (8, 7),
-                    (0, 8),
-                    (6, 9)
-                ],
-                map: vec![
-                    '.', '.', '.', '.', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
-                    '.', '.', '.', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
-                    '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#',
-                    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '.', '.',
-                    '^', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '.',
-                    '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
-                    '#', '.', '.', '.'
-                ],
-                start: guard,
-                guard
-            }
-        )
+        let mut expected = Map {
+            width: 10,
+            height: 10,
+            obstacles: vec![],
+            blocked: vec![false; 100],
+            map: vec![
+                '.', '.', '.', '.', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+                '.', '.', '.', '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+                '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#',
+                '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '.', '.',
+                '^', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '#', '.',
+                '#', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+                '#', '.', '.', '.',
+            ],
+            guards: vec![guard],
+            rules: Rules::default(),
+            start: guard,
+            guard,
+        };
+        for obstacle in [
+            (4, 0),
+            (9, 1),
+            (2, 3),
+            (7, 4),
+            (1, 6),
+            (8, 7),
+            (0, 8),
+            (6, 9),
+        ] {
+            expected.add_obstacle(obstacle);
+        }
+        assert_eq!(map, expected);
     }
 
     #[test]
@@ -134,7 +142,7 @@ mod test {
     }
 
     mod path {
-        use crate::Map;
+        use crate::{Direction, Map, StepOutcome};
 
         #[test]
         fn it_should_draw_path_north() {
@@ -207,6 +215,65 @@ mod test {
 
             assert_eq!(map.guard.position, (7, 6),);
         }
+
+        #[test]
+        fn it_should_turn_at_obstacle() {
+            let input = "#..
+                         ^..
+                         ...";
+            let mut map = Map::parse(input);
+
+            assert_eq!(map.step(), StepOutcome::Turned);
+            assert_eq!(map.guard.position, (0, 1));
+            assert_eq!(map.guard.facing, Direction::East);
+        }
+    }
+
+    mod bounds {
+        use crate::{run, Map, StepOutcome};
+
+        fn exit(input: &str) -> (usize, usize) {
+            let mut map = Map::parse(input);
+            let mut outcome = map.step();
+            while outcome == StepOutcome::Moved {
+                outcome = map.step();
+            }
+
+            assert_eq!(outcome, StepOutcome::Exited);
+            map.guard.position
+        }
+
+        #[test]
+        fn it_should_exit_north() {
+            assert_eq!(exit("...\n...\n^.."), (0, 0));
+        }
+
+        #[test]
+        fn it_should_exit_east() {
+            assert_eq!(exit("...\n...\n..>"), (2, 2));
+        }
+
+        #[test]
+        fn it_should_exit_south() {
+            assert_eq!(exit(".v.\n...\n..."), (1, 2));
+        }
+
+        #[test]
+        fn it_should_exit_west() {
+            assert_eq!(exit("...\n..<\n..."), (0, 1));
+        }
+
+        #[test]
+        fn it_should_count_edge_cells() {
+            // up the left edge, then across and out of the right
+            let input = "#...
+                         ....
+                         ....
+                         ^...";
+            let mut map = Map::parse(input);
+
+            assert_eq!(run(&mut map).unwrap().len(), 6);
+        }
     }
 
     #[test]
@@ -227,4 +294,180 @@ mod test {
 
         assert_eq!(result.unwrap().len(), 41);
     }
+
+    #[test]
+    fn it_should_find_loops() {
+        let input = "....#.....
+                         .........#
+                         ..........
+                         ..#.......
+                         .......#..
+                         ..........
+                         .#..^.....
+                         ........#.
+                         #.........
+                         ......#...";
+
+        let mut map = Map::parse(input);
+        let route = run(&mut map).unwrap();
+
+        assert_eq!(
+            check_for_loops(&route, &map),
+            HashSet::from([(3, 6), (6, 7), (7, 7), (1, 8), (3, 8), (7, 9)])
+        );
+    }
+
+    // what `check_for_loops` should find, one obstruction at a time on this thread
+    fn loops_one_by_one(route: &HashSet<Position>, map: &Map) -> HashSet<Position> {
+        route
+            .iter()
+            .filter(|pos| **pos != map.start.position)
+            .filter(|pos| {
+                let mut map = map.clone();
+                map.guard = map.start;
+                map.add_obstacle(**pos);
+                run(&mut map).is_none()
+            })
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn it_should_find_loops_with_other_rules() {
+        let input = "....#.....
+          .........#
+          ..........
+          ..#.......
+          .......#..
+          ..........
+          .#..^.....
+          ........#.
+          #.........
+          ......#...";
+
+        for rules in [
+            Rules {
+                turn: Turn::Left,
+                diagonal: false,
+            },
+            Rules {
+                turn: Turn::Right,
+                diagonal: true,
+            },
+        ] {
+            let mut map = Map::parse(input);
+            map.rules = rules;
+            let route = run(&mut map.clone()).unwrap();
+
+            assert!(route.len() > 1);
+            assert_eq!(
+                check_for_loops(&route, &map),
+                loops_one_by_one(&route, &map)
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_find_loops_on_short_routes() {
+        // fewer candidates than threads, or none at all
+        let mut map = Map::parse("^");
+        let route = run(&mut map).unwrap();
+        assert_eq!(route.len(), 1);
+        assert_eq!(check_for_loops(&route, &map), HashSet::new());
+
+        // blocking the only way out boxes the guard in
+        let input = ".#.
+          #^#
+          ...";
+        let mut map = Map::parse(input);
+        let route = run(&mut map).unwrap();
+        assert_eq!(route, HashSet::from([(1, 1), (1, 2)]));
+        assert_eq!(check_for_loops(&route, &map), HashSet::from([(1, 2)]));
+
+        map.rules.turn = Turn::Reverse;
+        map.guard = map.start;
+        let route = run(&mut map.clone()).unwrap();
+        assert_eq!(check_for_loops(&route, &map), HashSet::from([(1, 2)]));
+        assert_eq!(
+            check_for_loops(&route, &map),
+            loops_one_by_one(&route, &map)
+        );
+    }
+
+    mod outcome {
+        use std::collections::HashSet;
+
+        use crate::{patrol, run, Map, PatrolOutcome, Position};
+
+        const INPUT: &str = "....#.....
+                             .........#
+                             ..........
+                             ..#.......
+                             .......#..
+                             ..........
+                             .#..^.....
+                             ........#.
+                             #.........
+                             ......#...";
+
+        #[test]
+        fn it_should_report_exit() {
+            let mut map = Map::parse(INPUT);
+            let (route, outcome) = patrol(&mut map);
+
+            assert_eq!(
+                outcome,
+                PatrolOutcome::Exited {
+                    position: (7, 9),
+                    steps: 55
+                }
+            );
+            assert_eq!(route.len(), 55);
+            assert_eq!(outcome.to_string(), "leaves from (7, 9) after 55 steps");
+        }
+
+        #[test]
+        fn it_should_describe_loop() {
+            let mut map = Map::parse(INPUT);
+            map.add_obstacle((7, 9));
+            let (_, outcome) = patrol(&mut map);
+
+            assert_eq!(
+                outcome,
+                PatrolOutcome::Looped {
+                    tail: 37,
+                    cycle: 18,
+                    turns: vec![(1, 8), (1, 7), (7, 7), (7, 8)]
+                }
+            );
+            assert_eq!(outcome.cycle(), Some(18));
+
+            // straight into a loop from the start
+            let mut map = Map::parse(INPUT);
+            map.add_obstacle((3, 6));
+            let (_, outcome) = patrol(&mut map);
+
+            assert_eq!(
+                outcome.to_string(),
+                "loops after 0 steps, 22 steps round turning at (4, 1), (8, 1), (8, 6), (4, 6)"
+            );
+        }
+
+        #[test]
+        fn it_should_jump_to_the_same_route_as_stepping() {
+            for extra in [None, Some((7, 9)), Some((3, 6)), Some((5, 5))] {
+                let mut map = Map::parse(INPUT);
+                if let Some(extra) = extra {
+                    map.add_obstacle(extra);
+                }
+                let (route, outcome) = patrol(&mut map.clone());
+                let stepped: HashSet<Position> = route.iter().map(|guard| guard.position).collect();
+
+                match outcome {
+                    PatrolOutcome::Exited { .. } => assert_eq!(run(&mut map), Some(stepped)),
+                    PatrolOutcome::Looped { .. } => assert_eq!(run(&mut map), None),
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use crate::{
+    number::Number,
+    operator::{Inverse, Operator},
+};
+
+// applies everything on the stack that goes before `next`, then pushes the number so far
+// with `next` waiting on it. None if something on the way was undefined
+fn reduce<N: Number>(
+    stack: &[(N, Operator)],
+    mut current: N,
+    next: Option<Operator>,
+) -> Option<(Vec<(N, Operator)>, N)> {
+    let mut stack = stack.to_vec();
+    while let Some((left, operator)) = stack.last() {
+        // all left associative, ** included
+        if next.is_some_and(|next| operator.precedence() < next.precedence()) {
+            break;
+        }
+        current = operator.apply(left, &current)?;
+        stack.pop();
+    }
+    Some((stack, current))
+}
+
+// with no zeros, none of these ever make a sum smaller, wherever the next number goes, so
+// anything over the target is a dead end
+fn monotone<N: Number>(nums: &[N], operators: &[Operator]) -> bool {
+    nums.iter().all(|n| !n.is_zero())
+        && operators
+            .iter()
+            .all(|o| matches!(o, Operator::Add | Operator::Multiply | Operator::Concat))
+}
+
+// like `solve`, but with higher precedence operators going first, eg 1 + 6 * 6 = 37.
+// `stack` has the numbers waiting on something that goes before them
+pub fn precede<N: Number>(
+    target: &N,
+    rest: &[N],
+    stack: &[(N, Operator)],
+    current: N,
+    operators: &[Operator],
+) -> bool {
+    let prune = monotone(rest, operators);
+    let Some((next, rest)) = rest.split_first() else {
+        return reduce(stack, current, None).is_some_and(|(_, value)| value == *target);
+    };
+    // what it would come to if it stopped here
+    if prune && reduce(stack, current.clone(), None).is_some_and(|(_, value)| value > *target) {
+        return false;
+    }
+
+    operators.iter().any(|operator| {
+        reduce(stack, current.clone(), Some(*operator)).is_some_and(|(mut stack, current)| {
+            stack.push((current, *operator));
+            precede(target, rest, &stack, next.clone(), operators)
+        })
+    })
+}
+
+// whether the target can be made with brackets anywhere, eg (11 + 6) * 16 + 20 = 292.
+// Works out everything each run of numbers can come to, from pairs upwards, so it gets slow
+// with long lines and ||
+pub fn parenthesise<N: Number>(target: &N, nums: &[N], operators: &[Operator]) -> bool {
+    let prune = monotone(nums, operators);
+
+    let n = nums.len();
+    if n < 2 {
+        return nums == [target.clone()];
+    }
+
+    // values[start][end] is everything nums[start..=end] can come to
+    let mut values: Vec<Vec<HashSet<N>>> = vec![vec![HashSet::new(); n]; n];
+    for (i, num) in nums.iter().enumerate() {
+        values[i][i].insert(num.clone());
+    }
+
+    // all of them together would be the biggest set by far, so that one's never made
+    for length in 2..n {
+        for start in 0..=n - length {
+            let end = start + length - 1;
+            let mut found = HashSet::new();
+            for split in start..end {
+                for a in values[start][split].iter() {
+                    for b in values[split + 1][end].iter() {
+                        found.extend(
+                            operators
+                                .iter()
+                                .filter_map(|operator| operator.apply(a, b))
+                                .filter(|value| !prune || value <= target),
+                        );
+                    }
+                }
+            }
+            values[start][end] = found;
+        }
+    }
+
+    // instead it's worked back from the target for each way of splitting them in two
+    (0..n - 1).any(|split| {
+        let (left, right) = (&values[0][split], &values[split + 1][n - 1]);
+        right.iter().any(|b| {
+            operators
+                .iter()
+                .any(|operator| match operator.undo(target, b) {
+                    Inverse::None => false,
+                    Inverse::One(a) => left.contains(&a),
+                    Inverse::Any => !left.is_empty(),
+                })
+        })
+    })
+}
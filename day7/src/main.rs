@@ -1,5 +1,22 @@
-use std::{env::args, fs::read_to_string, io};
+use std::{
+    env::args,
+    fs::read_to_string,
+    io,
+    str::FromStr,
+    thread::{self, available_parallelism},
+};
 
+use evaluate::{parenthesise, precede};
+use memo::{closest, remember};
+use number::{Num, Number};
+use operator::{parse_operators, Inverse, Operator};
+use solution::{show, Show};
+
+mod evaluate;
+mod memo;
+mod number;
+mod operator;
+mod solution;
 mod test;
 
 fn main() -> Result<(), io::Error> {
@@ -7,97 +24,258 @@ fn main() -> Result<(), io::Error> {
 
     let filename = &args[1];
     let input = read_to_string(filename)?;
-    let combos = Calibration::parse(&input);
+    let combos: Vec<Calibration<Num>> = Calibration::parse(&input)?;
+    // backwards is much quicker, forwards and --search memo are there to compare against.
+    // --search precedence or --search parens work the sums out differently
+    let search = match args.iter().position(|arg| arg == "--search") {
+        Some(index) => args
+            .get(index + 1)
+            .expect("--search needs a name")
+            .parse()?,
+        None if args.iter().any(|arg| arg == "--forwards") => Search::Forwards,
+        None => Search::Backwards,
+    };
 
-    let solvable = find_solvable(&combos, false);
-    let total: u64 = get_total(&solvable);
-    println!("Part one: {}", total);
+    // eg --show all to print how each line is solved
+    let shown: Option<Show> = match args.iter().position(|arg| arg == "--show") {
+        Some(index) => Some(
+            args.get(index + 1)
+                .expect("--show needs one, all or count")
+                .parse()?,
+        ),
+        None => None,
+    };
+    // how near each line that can't be done gets
+    let explain = args.iter().any(|arg| arg == "--closest");
+    if (shown.is_some() || explain) && !search.left_to_right() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--show and --closest only work left to right",
+        ));
+    }
 
-    let part_two_solvable = find_solvable(&combos, true);
-    let part_two_total = get_total(&part_two_solvable);
-    println!("Part two: {}", part_two_total);
+    // eg --operators "- / ^" for a different set instead of the two parts
+    let sets = match args.iter().position(|arg| arg == "--operators") {
+        Some(index) => vec![(
+            "Total",
+            parse_operators(args.get(index + 1).expect("--operators needs a list"))?,
+        )],
+        None => vec![
+            ("Part one", Operator::PART_ONE.to_vec()),
+            ("Part two", Operator::PART_TWO.to_vec()),
+        ],
+    };
+
+    for (name, operators) in sets {
+        let solvable = find_solvable(&combos, &operators, search);
+        println!("{}: {}", name, get_total(&solvable)?);
+        if let Some(shown) = shown {
+            println!("{}", show(&combos, &operators, shown));
+        }
+        if explain {
+            let report = in_parallel(&combos, |c| {
+                (!solvable.contains(&c))
+                    .then(|| format!("{}: {}", c.target, closest(&c.target, &c.nums, &operators)))
+            });
+            if !report.is_empty() {
+                println!("{}", report.join("\n"));
+            }
+        }
+    }
 
     Ok(())
 }
 
-fn get_total(valid: &Vec<&Calibration>) -> u64 {
-    valid.iter().map(|c| c.target).fold(0, |acc, n| acc + n)
+fn get_total<N: Number>(valid: &[&Calibration<N>]) -> Result<N, io::Error> {
+    valid
+        .iter()
+        .try_fold(N::from_u64(0), |total, c| total.checked_add(&c.target))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("total is too big for a {}, try --features big", N::NAME),
+            )
+        })
 }
 
-fn find_solvable(combos: &Vec<Calibration>, use_concat: bool) -> Vec<&Calibration> {
-    combos
-        .iter()
-        .filter(|c| solve(c.target, &c.nums[1..], c.nums[0], use_concat))
-        .collect()
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Search {
+    // from the first number towards the target
+    Forwards,
+    // from the target back to the first number
+    Backwards,
+    // * before +, rather than left to right
+    Precedence,
+    // brackets anywhere
+    Parenthesised,
+    // forwards, without going over the same ground twice
+    Memoised,
+}
+
+impl Search {
+    // as opposed to working the sums out differently
+    fn left_to_right(&self) -> bool {
+        matches!(
+            self,
+            Search::Forwards | Search::Backwards | Search::Memoised
+        )
+    }
+}
+
+impl FromStr for Search {
+    type Err = io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "forwards" => Ok(Search::Forwards),
+            "backwards" => Ok(Search::Backwards),
+            "precedence" => Ok(Search::Precedence),
+            "parens" => Ok(Search::Parenthesised),
+            "memo" => Ok(Search::Memoised),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "unknown search {}, expected forwards, backwards, precedence, parens or memo",
+                    name
+                ),
+            )),
+        }
+    }
+}
+
+fn find_solvable<'a, N: Number>(
+    combos: &'a [Calibration<N>],
+    operators: &[Operator],
+    search: Search,
+) -> Vec<&'a Calibration<N>> {
+    in_parallel(combos, |c| {
+        let solvable = match search {
+            Search::Forwards => solve(&c.target, &c.nums[1..], c.nums[0].clone(), operators),
+            Search::Backwards => unsolve(&c.target, &c.nums, operators),
+            Search::Precedence => {
+                precede(&c.target, &c.nums[1..], &[], c.nums[0].clone(), operators)
+            }
+            Search::Parenthesised => parenthesise(&c.target, &c.nums, operators),
+            Search::Memoised => remember(&c.target, &c.nums, operators),
+        };
+        solvable.then_some(c)
+    })
+}
+
+// runs `f` over chunks of `items` on separate threads, keeping the results in order
+fn in_parallel<'a, T: Sync, R: Send>(
+    items: &'a [T],
+    f: impl Fn(&'a T) -> Option<R> + Sync,
+) -> Vec<R> {
+    let threads = available_parallelism().map_or(1, |n| n.get());
+    let size = items.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(size)
+            .map(|chunk| {
+                let f = &f;
+                scope.spawn(move || chunk.iter().filter_map(f).collect::<Vec<_>>())
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Solver panicked"))
+            .collect()
+    })
 }
 
 #[derive(Debug, PartialEq)]
-struct Calibration {
-    target: u64,
-    nums: Vec<u64>,
+struct Calibration<N = u64> {
+    target: N,
+    nums: Vec<N>,
 }
 
-impl Calibration {
-    fn parse(input: &str) -> Vec<Calibration> {
+impl<N: Number> Calibration<N> {
+    fn parse(input: &str) -> Result<Vec<Calibration<N>>, io::Error> {
+        let invalid = |line: usize, message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", line + 1, message),
+            )
+        };
+        // eg a number over u64::MAX, when built without --features big
+        let number = |line: usize, n: &str| {
+            n.parse::<N>().map_err(|_| {
+                invalid(
+                    line,
+                    format!("{} isn't a number that fits a {}", n, N::NAME),
+                )
+            })
+        };
+
         input
             .lines()
-            .map(|line| {
-                let split: Vec<&str> = line.trim().split(":").collect();
-                let target = split[0]
-                    .trim()
-                    .parse::<u64>()
-                    .expect("Target must be a number");
-                let nums = split[1]
+            .enumerate()
+            .map(|(index, line)| {
+                let Some((target, nums)) = line.trim().split_once(":") else {
+                    return Err(invalid(index, "expected target: numbers".to_string()));
+                };
+                let target = number(index, target.trim())?;
+                let nums = nums
                     .split_whitespace()
-                    .map(|n| n.parse::<u64>().expect("items must be numbers"))
-                    .collect();
+                    .map(|n| number(index, n))
+                    .collect::<Result<Vec<N>, io::Error>>()?;
+                if nums.is_empty() {
+                    return Err(invalid(index, "no numbers after the target".to_string()));
+                }
 
-                Calibration { target, nums }
+                Ok(Calibration { target, nums })
             })
             .collect()
     }
 }
 
-fn concat(a: u64, b: u64) -> u64 {
-    // str/parse is fairly slow
-    format!("{}{}", a, b)
-        .parse()
-        .expect("Couldn't concat numbers")
-}
-
-fn solve(target: u64, rest: &[u64], current: u64, use_concat: bool) -> bool {
+fn solve<N: Number>(target: &N, rest: &[N], current: N, operators: &[Operator]) -> bool {
     if rest.is_empty() {
-        return target == current;
+        return *target == current;
     }
 
     // rest.len == 1 = no early return, need to use all nums
-    if current * rest[0] == target && rest.len() == 1 {
-        return true;
-    } else if current + rest[0] == target && rest.len() == 1 {
-        return true;
-    } else if use_concat && concat(current, rest[0]) == target && rest.len() == 1 {
-        return true;
-    }
-
-    if current * rest[0] <= target {
-        if solve(target, &rest[1..], current * rest[0], use_concat) {
-            return true;
-        }
-    }
+    // if numbers only get bigger, anything over the target is a dead end
+    let grows = rest
+        .iter()
+        .all(|n| operators.iter().all(|operator| operator.grows(n)));
+    operators
+        .iter()
+        .filter_map(|operator| operator.apply(&current, &rest[0]))
+        .filter(|next| !grows || next <= target)
+        .any(|next| solve(target, &rest[1..], next, operators))
+}
 
-    if current + rest[0] <= target {
-        if solve(target, &rest[1..], current + rest[0], use_concat) {
-            return true;
-        }
+// whether the numbers can be combined at all, without anything being undefined
+fn evaluates<N: Number>(current: &N, rest: &[N], operators: &[Operator]) -> bool {
+    match rest.split_first() {
+        None => true,
+        Some((next, rest)) => operators
+            .iter()
+            .filter_map(|operator| operator.apply(current, next))
+            .any(|value| evaluates(&value, rest, operators)),
     }
+}
 
-    if use_concat {
-        if concat(current, rest[0]) <= target {
-            if solve(target, &rest[1..], concat(current, rest[0]), use_concat) {
-                return true;
-            }
-        }
+// works back from the target, undoing the last number each time. Most branches stop
+// straight away: eg the number can only have been added if it's no bigger than the target,
+// multiplied if it divides it, and concatenated if the target ends with it
+fn unsolve<N: Number>(target: &N, nums: &[N], operators: &[Operator]) -> bool {
+    let Some((last, rest)) = nums.split_last() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return target == last;
     }
 
-    false
+    operators
+        .iter()
+        .any(|operator| match operator.undo(target, last) {
+            Inverse::None => false,
+            Inverse::One(previous) => unsolve(&previous, rest, operators),
+            Inverse::Any => evaluates(&rest[0], &rest[1..], operators),
+        })
 }
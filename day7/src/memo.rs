@@ -0,0 +1,134 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{number::Number, operator::Operator};
+
+// `current` is what nums[..index] came to. Gives up on anything in `dead`, and adds to it
+fn forwards<N: Number>(
+    target: &N,
+    nums: &[N],
+    index: usize,
+    current: N,
+    operators: &[Operator],
+    dead: &mut HashSet<(usize, N)>,
+) -> bool {
+    if index == nums.len() {
+        return *target == current;
+    }
+    if dead.contains(&(index, current.clone())) {
+        return false;
+    }
+
+    let grows = nums[index..]
+        .iter()
+        .all(|n| operators.iter().all(|operator| operator.grows(n)));
+    let found = operators
+        .iter()
+        .filter_map(|operator| operator.apply(&current, &nums[index]))
+        .filter(|next| !grows || next <= target)
+        .any(|next| forwards(target, nums, index + 1, next, operators, dead));
+    if !found {
+        dead.insert((index, current));
+    }
+    found
+}
+
+// like `solve`, but remembering every (index, value) it's already given up on, since
+// different operators often get to the same number part way through
+pub fn remember<N: Number>(target: &N, nums: &[N], operators: &[Operator]) -> bool {
+    match nums.split_first() {
+        Some((first, _)) => forwards(
+            target,
+            nums,
+            1,
+            first.clone(),
+            operators,
+            &mut HashSet::new(),
+        ),
+        None => false,
+    }
+}
+
+// the nearest the numbers can get to the target from below and above, for explaining
+// why a line can't be done
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Closest<N> {
+    pub under: Option<N>,
+    pub over: Option<N>,
+}
+
+impl<N: Number> Closest<N> {
+    fn merge(self, other: Closest<N>) -> Closest<N> {
+        Closest {
+            under: self.under.into_iter().chain(other.under).max(),
+            over: self.over.into_iter().chain(other.over).min(),
+        }
+    }
+}
+
+impl<N: Number> Display for Closest<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.under {
+            Some(under) => write!(f, "closest {} under", under)?,
+            None => write!(f, "nothing under")?,
+        }
+        match &self.over {
+            Some(over) => write!(f, ", {} over", over),
+            None => write!(f, ", nothing over"),
+        }
+    }
+}
+
+impl<N> Default for Closest<N> {
+    fn default() -> Self {
+        Closest {
+            under: None,
+            over: None,
+        }
+    }
+}
+
+// same as `forwards`, but every full evaluation has to be looked at to find the nearest
+// over the target, so there's no pruning. Each (index, value) is still only worked out once
+fn reach<N: Number>(
+    target: &N,
+    nums: &[N],
+    index: usize,
+    current: N,
+    operators: &[Operator],
+    seen: &mut HashMap<(usize, N), Closest<N>>,
+) -> Closest<N> {
+    if index == nums.len() {
+        return Closest {
+            under: (current < *target).then(|| current.clone()),
+            over: (current > *target).then_some(current),
+        };
+    }
+    if let Some(closest) = seen.get(&(index, current.clone())) {
+        return closest.clone();
+    }
+
+    let closest = operators
+        .iter()
+        .filter_map(|operator| operator.apply(&current, &nums[index]))
+        .map(|next| reach(target, nums, index + 1, next, operators, seen))
+        .fold(Closest::default(), Closest::merge);
+    seen.insert((index, current), closest.clone());
+    closest
+}
+
+pub fn closest<N: Number>(target: &N, nums: &[N], operators: &[Operator]) -> Closest<N> {
+    match nums.split_first() {
+        Some((first, _)) => reach(
+            target,
+            nums,
+            1,
+            first.clone(),
+            operators,
+            &mut HashMap::new(),
+        ),
+        None => Closest::default(),
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+    str::FromStr,
+};
+
+#[cfg(feature = "big")]
+use num_bigint::BigUint;
+
+// what calibrations are read as. u64 unless built with --features big
+#[cfg(not(feature = "big"))]
+pub type Num = u64;
+#[cfg(feature = "big")]
+pub type Num = BigUint;
+
+// everything the solvers need from a number. The checked ones are None when the answer
+// isn't a whole number that fits
+pub trait Number: Clone + Debug + Display + Eq + Hash + Ord + FromStr + Send + Sync {
+    // for error messages
+    const NAME: &'static str;
+
+    fn from_u64(n: u64) -> Self;
+    fn checked_add(&self, b: &Self) -> Option<Self>;
+    fn checked_sub(&self, b: &Self) -> Option<Self>;
+    fn checked_mul(&self, b: &Self) -> Option<Self>;
+    // only when `b` divides it exactly
+    fn checked_div(&self, b: &Self) -> Option<Self>;
+    // None if `b` is zero
+    fn checked_rem(&self, b: &Self) -> Option<Self>;
+    fn xor(&self, b: &Self) -> Self;
+    fn checked_pow(&self, b: &Self) -> Option<Self>;
+    // the whole number `b`th root, if there is one. `b` is more than zero
+    fn root(&self, b: &Self) -> Option<Self>;
+    fn digits(&self) -> u32;
+    fn ten_pow(digits: u32) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::from_u64(0)
+    }
+}
+
+impl Number for u64 {
+    const NAME: &'static str = "u64";
+
+    fn from_u64(n: u64) -> Self {
+        n
+    }
+
+    fn checked_add(&self, b: &Self) -> Option<Self> {
+        u64::checked_add(*self, *b)
+    }
+
+    fn checked_sub(&self, b: &Self) -> Option<Self> {
+        u64::checked_sub(*self, *b)
+    }
+
+    fn checked_mul(&self, b: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *b)
+    }
+
+    fn checked_div(&self, b: &Self) -> Option<Self> {
+        u64::checked_rem(*self, *b)
+            .filter(|r| *r == 0)
+            .map(|_| self / b)
+    }
+
+    fn checked_rem(&self, b: &Self) -> Option<Self> {
+        u64::checked_rem(*self, *b)
+    }
+
+    fn xor(&self, b: &Self) -> Self {
+        self ^ b
+    }
+
+    fn checked_pow(&self, b: &Self) -> Option<Self> {
+        u64::checked_pow(*self, u32::try_from(*b).ok()?)
+    }
+
+    // binary search, since a guess from f64 is out by more than 1 past 2^53
+    fn root(&self, b: &Self) -> Option<Self> {
+        let (mut low, mut high) = (0, *self);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            match Number::checked_pow(&mid, b) {
+                Some(power) if power <= *self => low = mid,
+                _ => high = mid - 1,
+            }
+        }
+        (Number::checked_pow(&low, b) == Some(*self)).then_some(low)
+    }
+
+    fn digits(&self) -> u32 {
+        self.checked_ilog10().map_or(1, |d| d + 1)
+    }
+
+    fn ten_pow(digits: u32) -> Option<Self> {
+        10u64.checked_pow(digits)
+    }
+}
+
+// stops a power like 9 ** 999999999 using up all the memory
+#[cfg(feature = "big")]
+const MAX_BITS: u64 = 1 << 16;
+
+#[cfg(feature = "big")]
+impl Number for BigUint {
+    const NAME: &'static str = "big integer";
+
+    fn from_u64(n: u64) -> Self {
+        BigUint::from(n)
+    }
+
+    fn checked_add(&self, b: &Self) -> Option<Self> {
+        Some(self + b)
+    }
+
+    fn checked_sub(&self, b: &Self) -> Option<Self> {
+        (self >= b).then(|| self - b)
+    }
+
+    fn checked_mul(&self, b: &Self) -> Option<Self> {
+        Some(self * b)
+    }
+
+    fn checked_div(&self, b: &Self) -> Option<Self> {
+        Number::checked_rem(self, b)
+            .filter(|r| r.is_zero())
+            .map(|_| self / b)
+    }
+
+    fn checked_rem(&self, b: &Self) -> Option<Self> {
+        (!b.is_zero()).then(|| self % b)
+    }
+
+    fn xor(&self, b: &Self) -> Self {
+        self ^ b
+    }
+
+    fn checked_pow(&self, b: &Self) -> Option<Self> {
+        let b = u32::try_from(b).ok()?;
+        // 0 and 1 stay the same size
+        (self.bits() <= 1 || self.bits().saturating_mul(b as u64) <= MAX_BITS).then(|| self.pow(b))
+    }
+
+    fn root(&self, b: &Self) -> Option<Self> {
+        let b = u32::try_from(b).ok()?;
+        let root = self.nth_root(b);
+        (root.pow(b) == *self).then_some(root)
+    }
+
+    // 2^(bits - 1) <= self < 2^bits, so the estimate from the bits is right or one short
+    fn digits(&self) -> u32 {
+        if self.is_zero() {
+            return 1;
+        }
+        let digits = ((self.bits() - 1) as f64 * std::f64::consts::LOG10_2) as u32 + 1;
+        match *self >= BigUint::from(10u32).pow(digits) {
+            true => digits + 1,
+            false => digits,
+        }
+    }
+
+    fn ten_pow(digits: u32) -> Option<Self> {
+        Some(BigUint::from(10u32).pow(digits))
+    }
+}
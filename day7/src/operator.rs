@@ -0,0 +1,225 @@
+use std::{fmt::Display, io, str::FromStr};
+
+use crate::number::Number;
+
+// None if it's too big
+pub fn concat<N: Number>(a: &N, b: &N) -> Option<N> {
+    a.checked_mul(&N::ten_pow(b.digits())?)?.checked_add(b)
+}
+
+// what's left of `target` once the digits of `suffix` are taken off the end, if it ends
+// with them
+pub fn strip<N: Number>(target: &N, suffix: &N) -> Option<N> {
+    match N::ten_pow(suffix.digits()) {
+        Some(size) => match target.checked_rem(&size)? == *suffix {
+            true => target.checked_sub(suffix)?.checked_div(&size),
+            false => None,
+        },
+        // `suffix` is as long as the number type gets, so it has to be all of the target
+        None => (target == suffix).then(|| N::from_u64(0)),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Operator {
+    Add,
+    Multiply,
+    Concat,
+    Subtract,
+    // only when it divides exactly
+    Divide,
+    Xor,
+    Power,
+}
+
+// which left hand numbers give the target with a particular right hand one
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Inverse<N> {
+    None,
+    One(N),
+    // eg anything times zero
+    Any,
+}
+
+impl Operator {
+    pub const PART_ONE: [Operator; 2] = [Operator::Add, Operator::Multiply];
+    pub const PART_TWO: [Operator; 3] = [Operator::Add, Operator::Multiply, Operator::Concat];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Multiply => "*",
+            Operator::Concat => "||",
+            Operator::Subtract => "-",
+            Operator::Divide => "/",
+            Operator::Xor => "^",
+            Operator::Power => "**",
+        }
+    }
+
+    // None if it's undefined or too big
+    pub fn apply<N: Number>(&self, a: &N, b: &N) -> Option<N> {
+        match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Multiply => a.checked_mul(b),
+            Operator::Concat => concat(a, b),
+            Operator::Subtract => a.checked_sub(b),
+            Operator::Divide => a.checked_div(b),
+            Operator::Xor => Some(a.xor(b)),
+            Operator::Power => a.checked_pow(b),
+        }
+    }
+
+    pub fn undo<N: Number>(&self, target: &N, b: &N) -> Inverse<N> {
+        let found = |a: Option<N>| a.map_or(Inverse::None, Inverse::One);
+        match self {
+            Operator::Add => found(target.checked_sub(b)),
+            Operator::Multiply if b.is_zero() => match target.is_zero() {
+                true => Inverse::Any,
+                false => Inverse::None,
+            },
+            Operator::Multiply => found(target.checked_div(b)),
+            Operator::Concat => found(strip(target, b)),
+            Operator::Subtract => found(target.checked_add(b)),
+            Operator::Divide if b.is_zero() => Inverse::None,
+            Operator::Divide => found(target.checked_mul(b)),
+            Operator::Xor => found(Some(target.xor(b))),
+            Operator::Power if b.is_zero() => match *target == N::from_u64(1) {
+                true => Inverse::Any,
+                false => Inverse::None,
+            },
+            Operator::Power => found(target.root(b)),
+        }
+    }
+
+    // higher goes first when not strictly left to right. + and * are the usual way round,
+    // ^ is below + like in rust, and || is last, so it joins whole sums together
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Power => 4,
+            Operator::Multiply | Operator::Divide => 3,
+            Operator::Add | Operator::Subtract => 2,
+            Operator::Xor => 1,
+            Operator::Concat => 0,
+        }
+    }
+
+    // whether the answer is never smaller than the left hand number
+    pub fn grows<N: Number>(&self, b: &N) -> bool {
+        match self {
+            Operator::Add | Operator::Concat => true,
+            Operator::Multiply | Operator::Power => !b.is_zero(),
+            Operator::Subtract | Operator::Xor => b.is_zero(),
+            Operator::Divide => *b == N::from_u64(1),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FromStr for Operator {
+    type Err = io::Error;
+
+    fn from_str(symbol: &str) -> Result<Self, Self::Err> {
+        match symbol {
+            "+" => Ok(Operator::Add),
+            "*" => Ok(Operator::Multiply),
+            "||" => Ok(Operator::Concat),
+            "-" => Ok(Operator::Subtract),
+            "/" => Ok(Operator::Divide),
+            "^" | "xor" => Ok(Operator::Xor),
+            "**" | "pow" => Ok(Operator::Power),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "unknown operator {}, expected one of + * || - / ^ **",
+                    symbol
+                ),
+            )),
+        }
+    }
+}
+
+// separated by spaces or commas, eg "+ * ||" or "+,*,||"
+pub fn parse_operators(list: &str) -> Result<Vec<Operator>, io::Error> {
+    let mut operators: Vec<Operator> = vec![];
+    for symbol in list.split([' ', ',']).filter(|symbol| !symbol.is_empty()) {
+        let operator = symbol.parse()?;
+        if !operators.contains(&operator) {
+            operators.push(operator);
+        }
+    }
+
+    match operators.is_empty() {
+        true => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no operators given",
+        )),
+        false => Ok(operators),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::operator::{parse_operators, Inverse, Operator};
+
+    #[test]
+    fn it_should_undo_what_it_applies() {
+        let all = parse_operators("+ * || - / ^ **").unwrap();
+        assert_eq!(all.len(), 7);
+
+        for operator in all {
+            let big = [(1 << 62) + 1000, (1 << 32) + 1, u64::MAX - 1, u64::MAX];
+            for a in (0..40u64).chain(big) {
+                for b in 1..6 {
+                    if let Some(target) = operator.apply(&a, &b) {
+                        assert_eq!(
+                            operator.undo(&target, &b),
+                            Inverse::One(a),
+                            "{} {} {}",
+                            a,
+                            operator,
+                            b
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_handle_edge_cases() {
+        assert_eq!(Operator::Divide.apply(&7u64, &2), None);
+        assert_eq!(Operator::Divide.apply(&7u64, &0), None);
+        assert_eq!(Operator::Subtract.apply(&2u64, &7), None);
+        assert_eq!(Operator::Power.apply(&2u64, &64), None);
+        assert_eq!(Operator::Multiply.undo(&0u64, &0), Inverse::Any);
+        assert_eq!(Operator::Multiply.undo(&5u64, &0), Inverse::None);
+        assert_eq!(Operator::Power.undo(&1u64, &0), Inverse::Any);
+        assert_eq!(Operator::Power.undo(&10u64, &2), Inverse::None);
+        assert_eq!(Operator::Power.undo(&u64::MAX, &1), Inverse::One(u64::MAX));
+    }
+
+    #[test]
+    fn it_should_parse_operator_list() {
+        assert_eq!(
+            parse_operators("+,*,||").unwrap(),
+            Operator::PART_TWO.to_vec()
+        );
+        assert_eq!(
+            parse_operators("- / xor pow").unwrap(),
+            vec![
+                Operator::Subtract,
+                Operator::Divide,
+                Operator::Xor,
+                Operator::Power
+            ]
+        );
+        assert!(parse_operators("+ %").is_err());
+        assert!(parse_operators(" ").is_err());
+    }
+}
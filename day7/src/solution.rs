@@ -0,0 +1,191 @@
+use std::{fmt::Display, io, str::FromStr};
+
+use crate::{
+    number::Number,
+    operator::{Inverse, Operator},
+    Calibration,
+};
+
+// the operators between each pair of numbers, left to right
+#[derive(Debug, PartialEq)]
+pub struct Solution<'a, N> {
+    pub calibration: &'a Calibration<N>,
+    pub operators: Vec<Operator>,
+}
+
+impl<N: Number> Display for Solution<'_, N> {
+    // eg 190 = 10 * 19
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} = {}",
+            self.calibration.target, self.calibration.nums[0]
+        )?;
+        for (operator, n) in self.operators.iter().zip(&self.calibration.nums[1..]) {
+            write!(f, " {} {}", operator, n)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Show {
+    One,
+    All,
+    Count,
+}
+
+impl FromStr for Show {
+    type Err = io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "one" => Ok(Show::One),
+            "all" => Ok(Show::All),
+            "count" => Ok(Show::Count),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown show {}, expected one, all or count", name),
+            )),
+        }
+    }
+}
+
+// every way of combining `current` with the rest of the numbers, whatever it comes to.
+// Goes forwards, so unlike `assign` `chosen` is built left to right
+fn evaluations<N: Number>(
+    current: &N,
+    rest: &[N],
+    operators: &[Operator],
+    chosen: &mut Vec<Operator>,
+    found: &mut dyn FnMut(&[Operator]) -> bool,
+) -> bool {
+    let Some((next, rest)) = rest.split_first() else {
+        return found(chosen);
+    };
+
+    operators.iter().any(|operator| {
+        let Some(value) = operator.apply(current, next) else {
+            return false;
+        };
+        chosen.push(*operator);
+        let stop = evaluations(&value, rest, operators, chosen, found);
+        chosen.pop();
+        stop
+    })
+}
+
+// the same backwards search as `unsolve`, keeping track of which operators it undid.
+// `chosen` has the operators after `nums`, and `found` is called with each full set, and
+// returns true to stop looking
+fn assign<N: Number>(
+    target: &N,
+    nums: &[N],
+    operators: &[Operator],
+    chosen: &mut Vec<Operator>,
+    found: &mut dyn FnMut(&[Operator]) -> bool,
+) -> bool {
+    let Some((last, rest)) = nums.split_last() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return target == last && found(chosen);
+    }
+
+    operators.iter().any(|operator| {
+        chosen.insert(0, *operator);
+        let stop = match operator.undo(target, last) {
+            Inverse::None => false,
+            Inverse::One(previous) => assign(&previous, rest, operators, chosen, found),
+            Inverse::Any => {
+                let tail = chosen.clone();
+                evaluations(&rest[0], &rest[1..], operators, &mut vec![], &mut |head| {
+                    found(&[head, &tail[..]].concat())
+                })
+            }
+        };
+        chosen.remove(0);
+        stop
+    })
+}
+
+impl<N: Number> Calibration<N> {
+    pub fn solution(&self, operators: &[Operator]) -> Option<Solution<'_, N>> {
+        let mut solution = None;
+        assign(
+            &self.target,
+            &self.nums,
+            operators,
+            &mut vec![],
+            &mut |chosen| {
+                solution = Some(chosen.to_vec());
+                true
+            },
+        );
+        solution.map(|operators| Solution {
+            calibration: self,
+            operators,
+        })
+    }
+
+    pub fn solutions(&self, operators: &[Operator]) -> Vec<Solution<'_, N>> {
+        let mut solutions = vec![];
+        assign(
+            &self.target,
+            &self.nums,
+            operators,
+            &mut vec![],
+            &mut |chosen| {
+                solutions.push(chosen.to_vec());
+                false
+            },
+        );
+        solutions
+            .into_iter()
+            .map(|operators| Solution {
+                calibration: self,
+                operators,
+            })
+            .collect()
+    }
+
+    pub fn count_solutions(&self, operators: &[Operator]) -> usize {
+        let mut count = 0;
+        assign(
+            &self.target,
+            &self.nums,
+            operators,
+            &mut vec![],
+            &mut |_| {
+                count += 1;
+                false
+            },
+        );
+        count
+    }
+}
+
+// one line per solvable calibration
+pub fn show<N: Number>(combos: &[Calibration<N>], operators: &[Operator], show: Show) -> String {
+    combos
+        .iter()
+        .filter_map(|c| match show {
+            Show::One => c.solution(operators).map(|s| s.to_string()),
+            Show::All => {
+                let solutions = c.solutions(operators);
+                (!solutions.is_empty()).then(|| {
+                    solutions
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+            }
+            Show::Count => match c.count_solutions(operators) {
+                0 => None,
+                count => Some(format!("{}: {} ways", c.target, count)),
+            },
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
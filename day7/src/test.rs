@@ -1,17 +1,17 @@
 #[cfg(test)]
 mod test {
-    use crate::{find_solvable, solve, Calibration};
+    use crate::{find_solvable, operator::Operator, solve, Calibration, Search};
 
     #[test]
     fn it_should_solve_simple_case() {
         let input = "190: 10 19";
-        let calibrations = Calibration::parse(&input);
+        let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
         assert_eq!(
             solve(
-                calibrations[0].target,
+                &calibrations[0].target,
                 &calibrations[0].nums[1..],
                 calibrations[0].nums[0],
-                false
+                &Operator::PART_ONE
             ),
             true
         );
@@ -20,13 +20,13 @@ mod test {
     #[test]
     fn it_should_handle_1() {
         let input = "3358431: 72 52 7 96 873 1";
-        let calibrations = Calibration::parse(&input);
+        let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
         assert_eq!(
             solve(
-                calibrations[0].target,
+                &calibrations[0].target,
                 &calibrations[0].nums[1..],
                 calibrations[0].nums[0],
-                false
+                &Operator::PART_ONE
             ),
             true
         );
@@ -35,13 +35,13 @@ mod test {
     #[test]
     fn it_should_not_return_early() {
         let input = "4: 2 2 2";
-        let calibrations = Calibration::parse(&input);
+        let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
         assert_eq!(
             solve(
-                calibrations[0].target,
+                &calibrations[0].target,
                 &calibrations[0].nums[1..],
                 calibrations[0].nums[0],
-                false
+                &Operator::PART_ONE
             ),
             false
         );
@@ -50,13 +50,13 @@ mod test {
     #[test]
     fn it_should_handle_three_nums() {
         let input = "3267: 81 40 27";
-        let calibrations = Calibration::parse(&input);
+        let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
         assert_eq!(
             solve(
-                calibrations[0].target,
+                &calibrations[0].target,
                 &calibrations[0].nums[1..],
                 calibrations[0].nums[0],
-                false
+                &Operator::PART_ONE
             ),
             true
         );
@@ -65,13 +65,13 @@ mod test {
     #[test]
     fn it_should_handle_four_nums() {
         let input = "292: 11 6 16 20";
-        let calibrations = Calibration::parse(&input);
+        let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
         assert_eq!(
             solve(
-                calibrations[0].target,
+                &calibrations[0].target,
                 &calibrations[0].nums[1..],
                 calibrations[0].nums[0],
-                false
+                &Operator::PART_ONE
             ),
             true
         );
@@ -88,7 +88,7 @@ mod test {
           192: 17 8 14
           21037: 9 7 18 13
           292: 11 6 16 20";
-        let calibrations = Calibration::parse(&input);
+        let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
         let expected: Vec<Calibration> = vec![
             Calibration {
                 target: 190,
@@ -104,56 +104,481 @@ mod test {
             },
         ];
 
-        assert_eq!(
-            find_solvable(&calibrations, false),
-            // turn vec into vec<&_>
-            expected
-                .iter()
-                .filter(|_i| true)
-                .collect::<Vec<&Calibration>>()
-        );
+        for search in [Search::Forwards, Search::Backwards] {
+            assert_eq!(
+                find_solvable(&calibrations, &Operator::PART_ONE, search),
+                // turn vec into vec<&_>
+                expected
+                    .iter()
+                    .filter(|_i| true)
+                    .collect::<Vec<&Calibration>>()
+            );
+        }
     }
 
     mod concat {
-        use crate::{solve, Calibration};
+        use crate::{
+            operator::{concat, strip, Operator},
+            solve, Calibration,
+        };
+
+        #[test]
+        fn it_should_concat_numbers() {
+            assert_eq!(concat(&15u64, &6), Some(156));
+            assert_eq!(concat(&12u64, &345), Some(12345));
+            assert_eq!(concat(&1u64, &0), Some(10));
+            assert_eq!(concat(&0u64, &7), Some(7));
+            assert_eq!(concat(&1_000_000_000_000u64, &10_000_000), None);
+            assert_eq!(concat(&1u64, &u64::MAX), None);
+        }
+
+        #[test]
+        fn it_should_strip_suffix() {
+            assert_eq!(strip(&156u64, &6), Some(15));
+            assert_eq!(strip(&12345u64, &345), Some(12));
+            assert_eq!(strip(&12345u64, &45), Some(123));
+            assert_eq!(strip(&12345u64, &5), Some(1234));
+            assert_eq!(strip(&12345u64, &4), None);
+            assert_eq!(strip(&7u64, &7), Some(0));
+            assert_eq!(strip(&u64::MAX, &u64::MAX), Some(0));
+        }
 
         #[test]
         fn it_should_filter_using_concat() {
             let input = "156: 15 6";
-            let calibrations = Calibration::parse(&input);
+            let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
             assert_eq!(
                 solve(
-                    calibrations[0].target,
+                    &calibrations[0].target,
                     &calibrations[0].nums[1..],
                     calibrations[0].nums[0],
-                    true
+                    &Operator::PART_TWO
                 ),
                 true
             );
 
             let input = "7290: 6 8 6 15";
-            let calibrations = Calibration::parse(&input);
+            let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
             assert_eq!(
                 solve(
-                    calibrations[0].target,
+                    &calibrations[0].target,
                     &calibrations[0].nums[1..],
                     calibrations[0].nums[0],
-                    true
+                    &Operator::PART_TWO
                 ),
                 true
             );
 
             let input = "192: 17 8 14";
-            let calibrations = Calibration::parse(&input);
+            let calibrations: Vec<Calibration> = Calibration::parse(&input).unwrap();
             assert_eq!(
                 solve(
-                    calibrations[0].target,
+                    &calibrations[0].target,
                     &calibrations[0].nums[1..],
                     calibrations[0].nums[0],
-                    true
+                    &Operator::PART_TWO
                 ),
                 true
             );
         }
     }
+
+    mod backwards {
+        use crate::{operator::Operator, parse_operators, solve, unsolve, Calibration};
+
+        const INPUT: &str = "190: 10 19
+          3267: 81 40 27
+          83: 17 5
+          156: 15 6
+          7290: 6 8 6 15
+          161011: 16 10 13
+          192: 17 8 14
+          21037: 9 7 18 13
+          292: 11 6 16 20";
+
+        #[test]
+        fn it_should_agree_with_forwards() {
+            for calibration in Calibration::<u64>::parse(INPUT).unwrap() {
+                for operators in [
+                    Operator::PART_ONE.to_vec(),
+                    Operator::PART_TWO.to_vec(),
+                    parse_operators("- / ^ **").unwrap(),
+                ] {
+                    assert_eq!(
+                        unsolve(&calibration.target, &calibration.nums, &operators),
+                        solve(
+                            &calibration.target,
+                            &calibration.nums[1..],
+                            calibration.nums[0],
+                            &operators
+                        ),
+                        "{:?}",
+                        calibration
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn it_should_use_every_number() {
+            assert!(!unsolve(&4u64, &[2, 2, 2], &Operator::PART_ONE));
+            assert!(unsolve(&0u64, &[5, 0], &Operator::PART_ONE));
+            assert!(unsolve(&1230u64, &[1, 23, 0], &Operator::PART_TWO));
+        }
+
+        #[test]
+        fn it_should_not_overflow() {
+            let big = u64::MAX - 1;
+            assert!(!solve(&big, &[99999, 99999], 99999, &Operator::PART_TWO));
+            assert!(!unsolve(&big, &[99999, 99999, 99999], &Operator::PART_TWO));
+            assert!(unsolve(&big, &[big / 2, 2], &Operator::PART_ONE));
+            // too big to find the root with floats
+            let power = (1 << 62) + 1000;
+            assert!(unsolve(&power, &[power, 1], &[Operator::Power]));
+        }
+    }
+
+    mod operators {
+        use crate::{find_solvable, operator::Operator, parse_operators, Calibration, Search};
+
+        #[test]
+        fn it_should_use_chosen_operators() {
+            let input = "5: 20 4
+              3: 12 4 1
+              16: 2 4
+              6: 3 5
+              10: 7 3";
+            let calibrations: Vec<Calibration> = Calibration::parse(input).unwrap();
+
+            for search in [Search::Forwards, Search::Backwards] {
+                // 20 / 4, 12 / 4 / 1
+                let solvable = find_solvable(&calibrations, &parse_operators("/").unwrap(), search);
+                assert_eq!(solvable, vec![&calibrations[0], &calibrations[1]]);
+
+                // and 2 ** 4, 3 ^ 5
+                let operators = parse_operators("* / ** ^").unwrap();
+                let solvable = find_solvable(&calibrations, &operators, search);
+                assert_eq!(
+                    solvable,
+                    vec![
+                        &calibrations[0],
+                        &calibrations[1],
+                        &calibrations[2],
+                        &calibrations[3]
+                    ]
+                );
+
+                let solvable = find_solvable(&calibrations, &Operator::PART_ONE, search);
+                assert_eq!(solvable, vec![&calibrations[4]]);
+            }
+        }
+    }
+
+    mod solutions {
+        use crate::{
+            operator::Operator,
+            solution::{show, Show},
+            Calibration,
+        };
+
+        #[test]
+        fn it_should_render_solution() {
+            let calibrations: Vec<Calibration> =
+                Calibration::parse("190: 10 19\n83: 17 5").unwrap();
+
+            assert_eq!(
+                calibrations[0]
+                    .solution(&Operator::PART_ONE)
+                    .unwrap()
+                    .to_string(),
+                "190 = 10 * 19"
+            );
+            assert_eq!(calibrations[1].solution(&Operator::PART_TWO), None);
+        }
+
+        #[test]
+        fn it_should_find_every_solution() {
+            let calibrations: Vec<Calibration> =
+                Calibration::parse("3267: 81 40 27\n7290: 6 8 6 15").unwrap();
+
+            let solutions: Vec<String> = calibrations[0]
+                .solutions(&Operator::PART_ONE)
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            assert_eq!(
+                solutions,
+                vec!["3267 = 81 * 40 + 27", "3267 = 81 + 40 * 27"]
+            );
+            assert_eq!(
+                show(&calibrations, &Operator::PART_TWO, Show::One),
+                "3267 = 81 * 40 + 27\n7290 = 6 * 8 || 6 * 15"
+            );
+        }
+
+        #[test]
+        fn it_should_keep_order_before_times_zero() {
+            let calibrations: Vec<Calibration> = Calibration::parse("0: 2 5 3 0").unwrap();
+            let operators = [Operator::Subtract, Operator::Multiply];
+
+            // 2 - 5 would be below zero, so it has to start 2 * 5
+            assert_eq!(
+                calibrations[0].solution(&operators).unwrap().to_string(),
+                "0 = 2 * 5 - 3 * 0"
+            );
+            let solutions: Vec<String> = calibrations[0]
+                .solutions(&operators)
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            assert_eq!(solutions, vec!["0 = 2 * 5 - 3 * 0", "0 = 2 * 5 * 3 * 0"]);
+        }
+
+        #[test]
+        fn it_should_count_solutions() {
+            // anything times zero is zero, so whatever comes before it
+            let calibrations: Vec<Calibration> =
+                Calibration::parse("0: 3 4 0\n0: 0 0\n6: 1 1 1 3").unwrap();
+
+            assert_eq!(calibrations[0].count_solutions(&Operator::PART_ONE), 2);
+            assert_eq!(calibrations[1].count_solutions(&Operator::PART_ONE), 2);
+            // 1 + 1 + 1 + 3, 1 * 1 + 1 * 3 and 1 + 1 * 1 * 3, with nothing more from ||
+            assert_eq!(calibrations[2].count_solutions(&Operator::PART_TWO), 3);
+            assert_eq!(
+                show(&calibrations, &Operator::PART_ONE, Show::Count),
+                "0: 2 ways\n0: 2 ways\n6: 3 ways"
+            );
+        }
+    }
+
+    mod modes {
+        use crate::{
+            evaluate::{parenthesise, precede},
+            find_solvable,
+            operator::Operator,
+            Calibration, Search,
+        };
+
+        const INPUT: &str = "190: 10 19
+          3267: 81 40 27
+          292: 11 6 16 20
+          37: 1 6 6
+          156: 15 6
+          192: 17 8 14
+          83: 17 5";
+
+        fn solvable(search: Search, operators: &[Operator]) -> Vec<u64> {
+            find_solvable(
+                &Calibration::<u64>::parse(INPUT).unwrap(),
+                operators,
+                search,
+            )
+            .iter()
+            .map(|c| c.target)
+            .collect()
+        }
+
+        #[test]
+        fn it_should_disagree_on_order() {
+            // (11 + 6) * 16 + 20 left to right, but 11 + 96 + 20 with precedence
+            assert_eq!(
+                solvable(Search::Backwards, &Operator::PART_ONE),
+                vec![190, 3267, 292]
+            );
+            // 1 + 6 * 6 is 42 left to right
+            assert_eq!(
+                solvable(Search::Precedence, &Operator::PART_ONE),
+                vec![190, 3267, 37]
+            );
+            // anything either of the others can do, brackets can
+            assert_eq!(
+                solvable(Search::Parenthesised, &Operator::PART_ONE),
+                vec![190, 3267, 292, 37]
+            );
+            // so --show and --closest, which go left to right, can't be used with them
+            assert!(Search::Memoised.left_to_right());
+            assert!(!Search::Precedence.left_to_right());
+            assert!(!Search::Parenthesised.left_to_right());
+        }
+
+        #[test]
+        fn it_should_put_concat_last() {
+            // 17 || 8 + 14 is 17 || 22 with precedence
+            assert_eq!(
+                solvable(Search::Backwards, &Operator::PART_TWO),
+                vec![190, 3267, 292, 156, 192]
+            );
+            assert_eq!(
+                solvable(Search::Precedence, &Operator::PART_TWO),
+                vec![190, 3267, 37, 156]
+            );
+            assert_eq!(
+                solvable(Search::Parenthesised, &Operator::PART_TWO),
+                vec![190, 3267, 292, 37, 156, 192]
+            );
+        }
+
+        #[test]
+        fn it_should_handle_zero_and_undefined() {
+            // 5 * 0 + 3 with precedence is 3, left to right too
+            assert!(precede(&3u64, &[0, 3], &[], 5, &Operator::PART_ONE));
+            // 8 - 2 * 3 = 2 only with precedence, and 10 - (4 - 1) = 7 only with brackets
+            let operators = [Operator::Subtract, Operator::Multiply, Operator::Divide];
+            assert!(precede(&2u64, &[2, 3], &[], 8, &operators));
+            assert!(!precede(&7u64, &[4, 1], &[], 10, &operators));
+            assert!(parenthesise(&7u64, &[10, 4, 1], &operators));
+            // 2 - 3 is below zero, so there's nothing to make
+            assert!(!parenthesise(&0u64, &[2, 3], &[Operator::Subtract]));
+            assert!(parenthesise(&7u64, &[7], &operators));
+            assert!(!parenthesise(&7u64, &[], &operators));
+        }
+    }
+
+    mod numbers {
+        use crate::{find_solvable, get_total, operator::Operator, Calibration, Search};
+
+        #[test]
+        fn it_should_error_on_bad_lines() {
+            let error = Calibration::<u64>::parse("190: 10 19\n18446744073709551616: 1 2")
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                error,
+                "line 2: 18446744073709551616 isn't a number that fits a u64"
+            );
+
+            assert!(Calibration::<u64>::parse("190 10 19").is_err());
+            assert!(Calibration::<u64>::parse("190:").is_err());
+            assert!(Calibration::<u64>::parse("190: 10 x").is_err());
+        }
+
+        #[test]
+        fn it_should_error_when_total_overflows() {
+            let calibrations: Vec<Calibration> =
+                Calibration::parse("18446744073709551615: 18446744073709551615\n1: 1").unwrap();
+            let solvable = find_solvable(&calibrations, &Operator::PART_ONE, Search::Backwards);
+
+            assert_eq!(solvable.len(), 2);
+            assert!(get_total(&solvable).is_err());
+            assert_eq!(get_total(&solvable[..1]).unwrap(), u64::MAX);
+        }
+
+        #[cfg(feature = "big")]
+        #[test]
+        fn it_should_solve_big_numbers() {
+            use num_bigint::BigUint;
+
+            // 2^32 * 2^32, 2^64 * 2^64, 2^64 || 2^64, and one that can't be done
+            let input = "18446744073709551616: 4294967296 4294967296
+              340282366920938463463374607431768211456: 18446744073709551616 18446744073709551616
+              1844674407370955161618446744073709551616: 18446744073709551616 18446744073709551616
+              18446744073709551617: 18446744073709551616 2";
+            let calibrations: Vec<Calibration<BigUint>> = Calibration::parse(input).unwrap();
+
+            for search in [
+                Search::Forwards,
+                Search::Backwards,
+                Search::Precedence,
+                Search::Parenthesised,
+            ] {
+                let solvable = find_solvable(&calibrations, &Operator::PART_TWO, search);
+                assert_eq!(solvable.len(), 3);
+                assert_eq!(
+                    get_total(&solvable).unwrap().to_string(),
+                    "2184956774291893625100268095579187314688"
+                );
+            }
+        }
+
+        #[cfg(feature = "big")]
+        #[test]
+        fn it_should_count_big_digits() {
+            use num_bigint::BigUint;
+
+            use crate::number::Number;
+
+            assert_eq!(BigUint::from(0u32).digits(), 1);
+            for power in [1, 2, 19, 20, 100, 1000] {
+                let ten = BigUint::from(10u32).pow(power);
+                let two = BigUint::from(2u32).pow(power);
+                for n in [ten.clone() - 1u32, ten, two.clone() - 1u32, two] {
+                    assert_eq!(n.digits() as usize, n.to_string().len());
+                }
+            }
+        }
+    }
+
+    mod memo {
+        use crate::{
+            find_solvable,
+            memo::{closest, remember, Closest},
+            operator::Operator,
+            solve, Calibration, Search,
+        };
+
+        const INPUT: &str = "190: 10 19
+          3267: 81 40 27
+          83: 17 5
+          156: 15 6
+          7290: 6 8 6 15
+          161011: 16 10 13
+          192: 17 8 14
+          21037: 9 7 18 13
+          292: 11 6 16 20";
+
+        #[test]
+        fn it_should_agree_with_solve() {
+            let calibrations: Vec<Calibration> = Calibration::parse(INPUT).unwrap();
+
+            for operators in [Operator::PART_ONE.to_vec(), Operator::PART_TWO.to_vec()] {
+                for c in calibrations.iter() {
+                    assert_eq!(
+                        remember(&c.target, &c.nums, &operators),
+                        solve(&c.target, &c.nums[1..], c.nums[0], &operators)
+                    );
+                }
+                assert_eq!(
+                    find_solvable(&calibrations, &operators, Search::Memoised),
+                    find_solvable(&calibrations, &operators, Search::Backwards)
+                );
+            }
+            // lots of ways to 2, but only one to the end
+            assert!(remember(
+                &3,
+                &[1, 1, 1, 1, 1, 1, 1, 1, 1, 3],
+                &Operator::PART_ONE
+            ));
+        }
+
+        #[test]
+        fn it_should_find_closest() {
+            assert_eq!(
+                closest(&83, &[17, 5], &Operator::PART_ONE),
+                Closest {
+                    under: Some(22),
+                    over: Some(85)
+                }
+            );
+            assert_eq!(
+                closest(&161011, &[16, 10, 13], &Operator::PART_ONE).to_string(),
+                "closest 2080 under, nothing over"
+            );
+            assert_eq!(
+                closest(&161011, &[16, 10, 13], &Operator::PART_TWO).to_string(),
+                "closest 20930 under, 161013 over"
+            );
+            // solvable, but still something either side
+            assert_eq!(
+                closest(&190, &[10, 19], &Operator::PART_TWO),
+                Closest {
+                    under: Some(29),
+                    over: Some(1019)
+                }
+            );
+            assert_eq!(
+                closest(&1u64, &[], &Operator::PART_ONE).to_string(),
+                "nothing under, nothing over"
+            );
+        }
+    }
 }